@@ -0,0 +1,372 @@
+use std::{
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use num_bigint::BigInt;
+
+use crate::parsers::operations::Operator;
+
+use super::{
+    collections::{sorted_entries, Key},
+    operations::binary_operation,
+    RuntimeError, Value,
+};
+
+pub const PRINT_ID: &str = "msq_std::print()";
+pub const ADD_ID: &str = "msq_std::add()";
+pub const EQUALS_ID: &str = "msq_std::equals()";
+pub const SMALLER_ID: &str = "msq_std::smaller()";
+pub const DATE_ID: &str = "msq_std::date()";
+pub const BIG_ID: &str = "msq_std::big()";
+pub const INPUT_ID: &str = "msq_std::input()";
+pub const LEN_ID: &str = "msq_std::len()";
+pub const PUSH_ID: &str = "msq_std::push()";
+pub const POP_ID: &str = "msq_std::pop()";
+pub const KEYS_ID: &str = "msq_std::keys()";
+pub const VALUES_ID: &str = "msq_std::values()";
+pub const HAS_ID: &str = "msq_std::has()";
+pub const GET_ID: &str = "msq_std::get()";
+pub const PARSE_INT_ID: &str = "msq_std::parse_int()";
+pub const PARSE_FLOAT_ID: &str = "msq_std::parse_float()";
+
+pub const RESULT: &str = "Result";
+pub const OPTION: &str = "Option";
+pub const OK_ID: &str = "msq_std::Ok()";
+pub const ERR_ID: &str = "msq_std::Err()";
+pub const SOME_ID: &str = "msq_std::Some()";
+pub const NONE_ID: &str = "msq_std::None()";
+
+fn variant(enumeration: &str, variant: &str, values: Vec<Value>) -> Value {
+    Value::Variant {
+        enumeration: enumeration.to_string(),
+        variant: variant.to_string(),
+        values,
+    }
+}
+
+fn ok(value: Value) -> Value {
+    variant(RESULT, "Ok", vec![value])
+}
+
+fn err(message: impl Into<String>) -> Value {
+    variant(RESULT, "Err", vec![Value::String(message.into())])
+}
+
+fn option(value: Option<Value>) -> Value {
+    match value {
+        Some(value) => variant(OPTION, "Some", vec![value]),
+        None => variant(OPTION, "None", Vec::new()),
+    }
+}
+
+pub fn print(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    for arg in args {
+        print!("{}", arg);
+    }
+    println!();
+    Ok(None)
+}
+
+/// The number of milliseconds since the Unix epoch
+pub fn date(_args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let milliseconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| RuntimeError::new("The system clock is set before 1970"))?
+        .as_millis();
+
+    Ok(Some(Value::Int64(i64::try_from(milliseconds).map_err(
+        |_| RuntimeError::new("The date does not fit in an Int"),
+    )?)))
+}
+
+/// Converts an Int, or a String of digits, to a BigInt
+pub fn big(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    match args.as_slice() {
+        [Value::Int64(value)] => Ok(Some(Value::BigInt(BigInt::from(*value)))),
+        [Value::BigInt(value)] => Ok(Some(Value::BigInt(value.clone()))),
+        [Value::String(content)] => match content.trim().parse() {
+            Ok(value) => Ok(Some(Value::BigInt(value))),
+            Err(_) => Err(RuntimeError::new(format!(
+                "Cannot convert '{}' to a BigInt",
+                content.trim()
+            ))),
+        },
+        [value] => Err(RuntimeError::new(format!(
+            "Expected an Int or a String in function 'big', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(
+            "Expected one argument for function 'big'",
+        )),
+    }
+}
+
+/// Whether a value is a number, the values `add` and `smaller` take
+fn is_number(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Int64(_) | Value::Float64(_) | Value::BigInt(_)
+    )
+}
+
+/// Compares two numbers like `<` does
+pub fn smaller(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let Ok([left, right]) = <[Value; 2]>::try_from(args) else {
+        return Err(RuntimeError::new(
+            "Expected two arguments for function 'smaller'",
+        ));
+    };
+
+    if let Some(value) = [&left, &right].into_iter().find(|value| !is_number(value)) {
+        return Err(RuntimeError::new(format!(
+            "Function 'smaller' can only compare Ints, Floats or BigInts, found {}",
+            value.type_name()
+        )));
+    }
+
+    binary_operation(Operator::Smaller, left, right).map(Some)
+}
+
+pub fn input(_args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let mut input = String::new();
+
+    Ok(Some(match io::stdin().read_line(&mut input) {
+        Ok(_) => ok(Value::String(input)),
+        Err(error) => err(format!("Unable to read user input: {error}")),
+    }))
+}
+
+/// Adds numbers like `+` does
+pub fn add(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let mut args = args.into_iter();
+    let Some(first) = args.next() else {
+        return Err(RuntimeError::new(
+            "Expected at least one argument for function 'add'",
+        ));
+    };
+
+    if !is_number(&first) {
+        return Err(RuntimeError::new(format!(
+            "Function 'add' can only add Ints, Floats or BigInts, found {}",
+            first.type_name()
+        )));
+    }
+
+    args.try_fold(first, |sum, value| {
+        binary_operation(Operator::Addition, sum, value)
+    })
+    .map(Some)
+}
+
+pub fn equals(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::new(
+            "Expected two arguments for function 'equals'",
+        ));
+    }
+
+    let is_equals = args.first().unwrap().equals(args.last().unwrap())?;
+
+    Ok(Some(Value::Boolean(is_equals)))
+}
+
+pub fn len(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    match args.as_slice() {
+        [Value::List(items)] => Ok(Some(Value::Int64(items.len() as i64))),
+        [Value::Map(entries)] => Ok(Some(Value::Int64(entries.len() as i64))),
+        [Value::String(content)] => Ok(Some(Value::Int64(content.chars().count() as i64))),
+        [value] => Err(RuntimeError::new(format!(
+            "Expected a List, a Map or a String in function 'len', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(
+            "Expected one argument for function 'len'",
+        )),
+    }
+}
+
+pub fn push(list: &mut Value, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    match list {
+        Value::List(items) => {
+            items.extend(args);
+            Ok(None)
+        }
+        value => Err(RuntimeError::new(format!(
+            "Expected a List in function 'push', found {}",
+            value.type_name()
+        ))),
+    }
+}
+
+pub fn pop(list: &mut Value, args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    if !args.is_empty() {
+        return Err(RuntimeError::new(
+            "Expected one argument for function 'pop'",
+        ));
+    }
+
+    match list {
+        Value::List(items) => match items.pop() {
+            Some(item) => Ok(Some(item)),
+            None => Err(RuntimeError::new("Cannot pop from an empty List")),
+        },
+        value => Err(RuntimeError::new(format!(
+            "Expected a List in function 'pop', found {}",
+            value.type_name()
+        ))),
+    }
+}
+
+/// Checks that a builtin was called with a single map, and returns its entries sorted by key
+fn map_entries<'a>(
+    name: &str,
+    args: &'a [Value],
+) -> Result<Vec<(&'a Key, &'a Value)>, RuntimeError> {
+    match args {
+        [Value::Map(entries)] => Ok(sorted_entries(entries)),
+        [value] => Err(RuntimeError::new(format!(
+            "Expected a Map in function '{name}', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(format!(
+            "Expected one argument for function '{name}'"
+        ))),
+    }
+}
+
+pub fn keys(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let entries = map_entries("keys", &args)?;
+    Ok(Some(Value::List(
+        entries.into_iter().map(|(key, _)| key.to_value()).collect(),
+    )))
+}
+
+pub fn values(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let entries = map_entries("values", &args)?;
+    Ok(Some(Value::List(
+        entries
+            .into_iter()
+            .map(|(_, value)| value.clone())
+            .collect(),
+    )))
+}
+
+pub fn has(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    match args.as_slice() {
+        [Value::Map(entries), key] => {
+            let key = Key::from_value(key.clone())?;
+            Ok(Some(Value::Boolean(entries.contains_key(&key))))
+        }
+        [value, _] => Err(RuntimeError::new(format!(
+            "Expected a Map in function 'has', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(
+            "Expected two arguments for function 'has'",
+        )),
+    }
+}
+
+/// Reads the element of a list at an index or the value of a map at a key, `None` when there
+/// isn't any
+pub fn get(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    match args.as_slice() {
+        [Value::List(items), Value::Int64(position)] => Ok(Some(option(
+            usize::try_from(*position)
+                .ok()
+                .and_then(|position| items.get(position))
+                .cloned(),
+        ))),
+        [Value::List(_), index] => Err(RuntimeError::new(format!(
+            "Expected an Int to index a List, found {}",
+            index.type_name()
+        ))),
+        [Value::Map(entries), key] => {
+            let key = Key::from_value(key.clone())?;
+            Ok(Some(option(entries.get(&key).cloned())))
+        }
+        [value, _] => Err(RuntimeError::new(format!(
+            "Expected a List or a Map in function 'get', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(
+            "Expected two arguments for function 'get'",
+        )),
+    }
+}
+
+/// Checks that a builtin was called with a single string
+fn string_argument<'a>(name: &str, args: &'a [Value]) -> Result<&'a str, RuntimeError> {
+    match args {
+        [Value::String(content)] => Ok(content),
+        [value] => Err(RuntimeError::new(format!(
+            "Expected a String in function '{name}', found {}",
+            value.type_name()
+        ))),
+        _ => Err(RuntimeError::new(format!(
+            "Expected one argument for function '{name}'"
+        ))),
+    }
+}
+
+pub fn parse_int(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let content = string_argument("parse_int", &args)?;
+
+    Ok(Some(match content.trim().parse() {
+        Ok(number) => ok(Value::Int64(number)),
+        Err(_) => err(format!("Cannot parse '{}' as an Int", content.trim())),
+    }))
+}
+
+pub fn parse_float(args: Vec<Value>) -> Result<Option<Value>, RuntimeError> {
+    let content = string_argument("parse_float", &args)?;
+
+    Ok(Some(match content.trim().parse() {
+        Ok(number) => ok(Value::Float64(number)),
+        Err(_) => err(format!("Cannot parse '{}' as a Float", content.trim())),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big_int(value: &str) -> Value {
+        Value::BigInt(value.parse().unwrap())
+    }
+
+    #[test]
+    pub fn numbers() {
+        assert_eq!(
+            add(vec![Value::Int64(i64::MAX), Value::Int64(1)]).unwrap_err().message,
+            "Integer overflow: 9223372036854775807 + 1 does not fit in an Int, use big() for larger numbers"
+        );
+        assert_eq!(
+            add(vec![
+                big_int("9223372036854775807"),
+                Value::Int64(1),
+                Value::Int64(2)
+            ]),
+            Ok(Some(big_int("9223372036854775810")))
+        );
+        assert_eq!(
+            add(vec![Value::Float64(0.5), Value::Float64(1.0)]),
+            Ok(Some(Value::Float64(1.5)))
+        );
+        assert!(add(vec![big_int("1"), Value::Float64(1.5)]).is_err());
+        assert!(add(vec![Value::String("a".to_string())]).is_err());
+
+        assert_eq!(
+            smaller(vec![Value::Int64(1), big_int("2")]),
+            Ok(Some(Value::Boolean(true)))
+        );
+        assert_eq!(
+            smaller(vec![big_int("3"), Value::Int64(2)]),
+            Ok(Some(Value::Boolean(false)))
+        );
+        assert!(smaller(vec![big_int("1"), Value::Float64(1.5)]).is_err());
+        assert!(smaller(vec![Value::Boolean(true), Value::Int64(1)]).is_err());
+        assert!(smaller(vec![Value::Int64(1)]).is_err());
+    }
+}
@@ -0,0 +1,1166 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parsers::{
+    closure::Closure,
+    function_call::FunctionCall,
+    list::Accessor,
+    number::Number,
+    operations::{Operator, UnaryOperator},
+    statements::Statement,
+    types::Type,
+    variable::Assignement,
+};
+
+use super::{
+    builtins::{OPTION, RESULT},
+    CompileError,
+};
+
+/// What is known about a function before calling it
+#[derive(Debug, Clone)]
+struct Signature {
+    parameters: Vec<Type>,
+    /// Whether the extra arguments of a call are collected into a list
+    variadic: bool,
+    returns: Type,
+}
+
+#[derive(Debug, Clone)]
+enum Callable {
+    /// A native function, its arguments are checked by `Checker::builtin`
+    Builtin,
+    Function(Signature),
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    value: Type,
+    /// Whether the type was written by the programmer, values of other types can't be assigned
+    /// to an annotated variable. The type of the other ones is widened instead
+    annotated: bool,
+    callable: Option<Callable>,
+}
+
+impl Binding {
+    fn inferred(value: Type) -> Self {
+        Binding {
+            value,
+            annotated: false,
+            callable: None,
+        }
+    }
+}
+
+/// The function whose body is being checked
+struct Function {
+    name: String,
+    returns: Option<Type>,
+    /// The types of the values returned so far, the return type is inferred from them when it
+    /// isn't annotated
+    returned: Vec<Type>,
+}
+
+/// Whether a value of type `found` can be used where a value of type `expected` is
+fn compatible(expected: &Type, found: &Type) -> bool {
+    match (expected, found) {
+        (Type::Any, _) | (_, Type::Any) => true,
+        (Type::List(expected), Type::List(found))
+        | (Type::Reference(expected), Type::Reference(found)) => compatible(expected, found),
+        (Type::Map(expected_key, expected_value), Type::Map(found_key, found_value)) => {
+            compatible(expected_key, found_key) && compatible(expected_value, found_value)
+        }
+        (Type::Tuple(expected), Type::Tuple(found)) => {
+            expected.len() == found.len()
+                && expected
+                    .iter()
+                    .zip(found)
+                    .all(|(expected, found)| compatible(expected, found))
+        }
+        (expected, found) => expected == found,
+    }
+}
+
+/// The most precise type holding the values of both types
+fn join(left: &Type, right: &Type) -> Type {
+    match (left, right) {
+        (left, right) if left == right => left.clone(),
+        (Type::List(left), Type::List(right)) => Type::List(Box::new(join(left, right))),
+        (Type::Reference(left), Type::Reference(right)) => {
+            Type::Reference(Box::new(join(left, right)))
+        }
+        (Type::Map(left_key, left_value), Type::Map(right_key, right_value)) => Type::Map(
+            Box::new(join(left_key, right_key)),
+            Box::new(join(left_value, right_value)),
+        ),
+        (Type::Tuple(left), Type::Tuple(right)) if left.len() == right.len() => Type::Tuple(
+            left.iter()
+                .zip(right)
+                .map(|(left, right)| join(left, right))
+                .collect(),
+        ),
+        _ => Type::Any,
+    }
+}
+
+fn is_numeric(value: &Type) -> bool {
+    matches!(value, Type::Int | Type::Float | Type::Any)
+}
+
+/// Whether a value can be a number of any kind, the values `add` and `smaller` take
+fn is_number(value: &Type) -> bool {
+    is_numeric(value) || *value == Type::BigInt
+}
+
+fn mismatch(operator: Operator, left: &Type, right: &Type) -> CompileError {
+    CompileError::new(format!("Cannot apply '{operator}' to {left} and {right}"))
+}
+
+/// Follows the rules of `binary_operation`, so that the operations it would reject are rejected
+/// before the program runs
+fn operation(operator: Operator, left: Type, right: Type) -> Result<Type, CompileError> {
+    match operator {
+        Operator::Addition
+        | Operator::Substraction
+        | Operator::Multiplication
+        | Operator::Division => match (&left, &right) {
+            (Type::Int, Type::Int) => Ok(Type::Int),
+            (Type::Float, Type::Float) => Ok(Type::Float),
+            (Type::BigInt, Type::BigInt | Type::Int) | (Type::Int, Type::BigInt) => {
+                Ok(Type::BigInt)
+            }
+            // A value of any type can be a BigInt, which turns Ints into BigInts
+            (Type::Any, other) | (other, Type::Any)
+                if matches!(other, Type::Int | Type::Float | Type::BigInt | Type::Any) =>
+            {
+                Ok(Type::Any)
+            }
+            _ => Err(mismatch(operator, &left, &right)),
+        },
+        Operator::Smaller
+        | Operator::SmallerOrEquals
+        | Operator::Greater
+        | Operator::GreaterOrEquals => match (&left, &right) {
+            (left, right) if is_numeric(left) && is_numeric(right) => Ok(Type::Boolean),
+            (Type::BigInt, Type::BigInt | Type::Int | Type::Any)
+            | (Type::Int | Type::Any, Type::BigInt) => Ok(Type::Boolean),
+            (Type::String | Type::Any, Type::String | Type::Any) => Ok(Type::Boolean),
+            _ => Err(mismatch(operator, &left, &right)),
+        },
+        Operator::Equals | Operator::NotEquals => Ok(Type::Boolean),
+        Operator::And | Operator::Or => match (&left, &right) {
+            (Type::Boolean | Type::Any, Type::Boolean | Type::Any) => Ok(Type::Boolean),
+            _ => Err(mismatch(operator, &left, &right)),
+        },
+    }
+}
+
+fn unary_operation(operator: UnaryOperator, value: Type) -> Result<Type, CompileError> {
+    match (operator, value) {
+        (UnaryOperator::Negation, value) if is_numeric(&value) || value == Type::BigInt => {
+            Ok(value)
+        }
+        (UnaryOperator::Not, Type::Boolean | Type::Any) => Ok(Type::Boolean),
+        (operator, value) => Err(CompileError::new(format!(
+            "Cannot apply '{operator}' to {value}"
+        ))),
+    }
+}
+
+/// Checks the types of a program before it is compiled. Values nothing is known about have the
+/// `Any` type and are accepted everywhere, so unannotated code is only rejected when the types
+/// inferred from literals and annotations can't work together
+pub struct Checker {
+    scopes: Vec<HashMap<String, Binding>>,
+    /// The fields of the record types
+    records: HashMap<String, Vec<String>>,
+    /// The record and sum types declared by the program
+    types: HashSet<String>,
+    functions: Vec<Function>,
+    /// Problems that don't prevent the program from running
+    pub warnings: Vec<String>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let mut builtins: HashMap<String, Binding> = [
+            "print",
+            "add",
+            "equals",
+            "smaller",
+            "date",
+            "big",
+            "input",
+            "len",
+            "push",
+            "pop",
+            "keys",
+            "values",
+            "has",
+            "get",
+            "parse_int",
+            "parse_float",
+        ]
+        .into_iter()
+        .map(|name| {
+            (
+                name.to_string(),
+                Binding {
+                    value: Type::Closure,
+                    annotated: true,
+                    callable: Some(Callable::Builtin),
+                },
+            )
+        })
+        .collect();
+
+        for (variant, enumeration) in [("Ok", RESULT), ("Err", RESULT), ("Some", OPTION)] {
+            builtins.insert(
+                variant.to_string(),
+                Binding {
+                    value: Type::Closure,
+                    annotated: true,
+                    callable: Some(Callable::Function(Signature {
+                        parameters: vec![Type::Any],
+                        variadic: false,
+                        returns: Type::Named(enumeration.to_string()),
+                    })),
+                },
+            );
+        }
+        builtins.insert(
+            "None".to_string(),
+            Binding::inferred(Type::Named(OPTION.to_string())),
+        );
+
+        Checker {
+            scopes: vec![builtins],
+            records: HashMap::new(),
+            types: HashSet::from([RESULT.to_string(), OPTION.to_string()]),
+            functions: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Checks the statements of a file, errors name the function they are found in
+    pub fn check(&mut self, statements: &[Statement]) -> Result<(), CompileError> {
+        for statement in statements {
+            let declaration = match statement {
+                Statement::Export(declaration) => declaration.as_ref(),
+                statement => statement,
+            };
+
+            match declaration {
+                Statement::TypeDeclaration(declaration) => {
+                    self.types.insert(declaration.name.clone());
+                }
+                Statement::EnumDeclaration(declaration) => {
+                    self.types.insert(declaration.name.clone());
+                }
+                _ => {}
+            }
+        }
+
+        self.scopes.push(HashMap::new());
+        let result = self.statements(statements);
+        self.scopes.pop();
+
+        result.map_err(|error| match self.functions.last() {
+            Some(function) => CompileError::new(format!(
+                "{} (in function '{}')",
+                error.message, function.name
+            )),
+            None => error,
+        })
+    }
+
+    /// Declares types that are not declared by the checked program, like the ones of the modules
+    /// it imports
+    pub fn declare_types(&mut self, types: Vec<String>) {
+        self.types.extend(types);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    fn declare(&mut self, name: &str, binding: Binding) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), binding);
+    }
+
+    /// Checks that the types named by an annotation exist
+    fn annotation(&self, annotation: &Type) -> Result<(), CompileError> {
+        match annotation {
+            Type::Named(name) if !self.types.contains(name) => {
+                Err(CompileError::new(format!("Unknown type '{name}'")))
+            }
+            Type::List(item) => self.annotation(item),
+            Type::Map(key, value) => {
+                self.annotation(key)?;
+                self.annotation(value)
+            }
+            Type::Tuple(items) => items.iter().try_for_each(|item| self.annotation(item)),
+            Type::Reference(value) => self.annotation(value),
+            _ => Ok(()),
+        }
+    }
+
+    fn scoped(&mut self, statements: &[Statement]) -> Result<(), CompileError> {
+        self.scopes.push(HashMap::new());
+        let result = self.statements(statements);
+        self.scopes.pop();
+        result
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Result<(), CompileError> {
+        statements
+            .iter()
+            .try_for_each(|statement| self.statement(statement))
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        match statement {
+            Statement::Assignement(assignement) if assignement.declaration => {
+                self.declaration(assignement)?
+            }
+            Statement::Assignement(assignement) => self.assignement(assignement)?,
+            Statement::Destructuring(destructuring) => {
+                let found = self.expression(&destructuring.value)?;
+                let names = destructuring.names.len();
+
+                let (items, annotated) = match &destructuring.annotation {
+                    Some(annotation) => {
+                        self.annotation(annotation)?;
+                        match annotation {
+                            Type::Tuple(items)
+                                if items.len() == names && compatible(annotation, &found) =>
+                            {
+                                (items.clone(), true)
+                            }
+                            _ => {
+                                return Err(CompileError::new(format!(
+                                    "Cannot destructure {found} into {names} variables of type \
+                                     {annotation}"
+                                )))
+                            }
+                        }
+                    }
+                    None => match found {
+                        Type::Tuple(items) if items.len() == names => (items, false),
+                        _ => (vec![Type::Any; names], false),
+                    },
+                };
+
+                for (name, item) in destructuring.names.iter().zip(items) {
+                    self.declare(
+                        name,
+                        Binding {
+                            value: item,
+                            annotated,
+                            callable: None,
+                        },
+                    );
+                }
+            }
+            Statement::FunctionDeclaration(declaration) => {
+                // Declared before its body is checked so that it can call itself
+                let signature = Signature {
+                    parameters: declaration
+                        .closure
+                        .types
+                        .iter()
+                        .map(|annotation| annotation.clone().unwrap_or(Type::Any))
+                        .collect(),
+                    variadic: declaration.closure.rest.is_some(),
+                    returns: declaration.closure.returns.clone().unwrap_or(Type::Any),
+                };
+                self.declare(
+                    &declaration.name,
+                    Binding {
+                        value: Type::Closure,
+                        annotated: true,
+                        callable: Some(Callable::Function(signature)),
+                    },
+                );
+
+                let signature = self.closure(&declaration.name, &declaration.closure)?;
+                self.lookup_mut(&declaration.name).unwrap().callable =
+                    Some(Callable::Function(signature));
+            }
+            Statement::Return(value) => {
+                let found = self.expression(value)?;
+                self.returned(found)?;
+            }
+            Statement::Conditional(conditional) => {
+                let (condition, body) = conditional.main_condition.as_ref();
+                self.condition(condition, "an if")?;
+                self.scoped(&body.body)?;
+
+                for (condition, body) in conditional.alternates.iter() {
+                    self.condition(condition, "an if")?;
+                    self.scoped(&body.body)?;
+                }
+                if let Some(fallback) = &conditional.fallback {
+                    self.scoped(&fallback.body)?;
+                }
+            }
+            Statement::While(while_loop) => {
+                self.condition(&while_loop.condition, "a while")?;
+                self.scoped(&while_loop.body.body)?;
+            }
+            Statement::Match(statement) => {
+                self.expression(&statement.value)?;
+
+                for arm in statement.arms.iter() {
+                    self.scopes.push(HashMap::new());
+                    for name in arm.names.iter() {
+                        self.declare(name, Binding::inferred(Type::Any));
+                    }
+                    let result = self.statements(&arm.body.body);
+                    self.scopes.pop();
+                    result?;
+                }
+            }
+            Statement::TypeDeclaration(declaration) => {
+                self.records
+                    .insert(declaration.name.clone(), declaration.fields.clone());
+                self.declare(
+                    &declaration.name,
+                    Binding {
+                        value: Type::Closure,
+                        annotated: true,
+                        callable: Some(Callable::Function(Signature {
+                            parameters: vec![Type::Any; declaration.fields.len()],
+                            variadic: false,
+                            returns: Type::Named(declaration.name.clone()),
+                        })),
+                    },
+                );
+            }
+            Statement::EnumDeclaration(declaration) => {
+                let enumeration = Type::Named(declaration.name.clone());
+
+                for variant in declaration.variants.iter() {
+                    let binding = match variant.fields.len() {
+                        0 => Binding::inferred(enumeration.clone()),
+                        fields => Binding {
+                            value: Type::Closure,
+                            annotated: true,
+                            callable: Some(Callable::Function(Signature {
+                                parameters: vec![Type::Any; fields],
+                                variadic: false,
+                                returns: enumeration.clone(),
+                            })),
+                        },
+                    };
+                    self.declare(&variant.name, binding);
+                }
+            }
+            Statement::Export(declaration) => self.statement(declaration)?,
+            Statement::Import(_) | Statement::Break | Statement::Continue => {}
+            Statement::FunctionCall(call) => {
+                if self.call(call)? == Type::Named(RESULT.to_string()) {
+                    let warning = format!(
+                        "The Result returned by '{}' is discarded, handle it with a match or '?'",
+                        call.name
+                    );
+                    self.warnings.push(match self.functions.last() {
+                        Some(function) => format!("{warning} (in function '{}')", function.name),
+                        None => warning,
+                    });
+                }
+            }
+            statement => {
+                self.expression(statement)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn condition(&mut self, condition: &Statement, statement: &str) -> Result<(), CompileError> {
+        match self.expression(condition)? {
+            Type::Boolean | Type::Any => Ok(()),
+            found => Err(CompileError::new(format!(
+                "Expected a Boolean as the condition of {statement}, found {found}"
+            ))),
+        }
+    }
+
+    fn declaration(&mut self, assignement: &Assignement) -> Result<(), CompileError> {
+        let (found, callable) = self.value(&assignement.value)?;
+
+        let binding = match &assignement.annotation {
+            Some(annotation) => {
+                self.annotation(annotation)?;
+                if !compatible(annotation, &found) {
+                    return Err(CompileError::new(format!(
+                        "Cannot assign {found} to '{}' of type {annotation}",
+                        assignement.name
+                    )));
+                }
+
+                Binding {
+                    value: annotation.clone(),
+                    annotated: true,
+                    callable,
+                }
+            }
+            None => Binding {
+                value: found,
+                annotated: false,
+                callable,
+            },
+        };
+
+        self.declare(&assignement.name, binding);
+        Ok(())
+    }
+
+    fn assignement(&mut self, assignement: &Assignement) -> Result<(), CompileError> {
+        let (found, callable) = self.value(&assignement.value)?;
+
+        let mut indexes = Vec::new();
+        for accessor in assignement.accessors.iter() {
+            if let Accessor::Index(index) = accessor {
+                indexes.push(self.expression(index)?);
+            }
+        }
+
+        let Some(binding) = self.lookup_mut(&assignement.name) else {
+            return Ok(());
+        };
+
+        if assignement.dereference {
+            return match (assignement.accessors.as_slice(), &binding.value) {
+                ([], Type::Reference(expected)) if !compatible(expected, &found) => {
+                    Err(CompileError::new(format!(
+                        "Cannot assign {found} through '{}' of type {}",
+                        assignement.name, binding.value
+                    )))
+                }
+                (_, Type::Reference(_) | Type::Any) => Ok(()),
+                (_, value) => Err(CompileError::new(format!(
+                    "Expected a Reference after 'deref', found {value}"
+                ))),
+            };
+        }
+
+        match (assignement.accessors.as_slice(), &binding.value) {
+            ([], expected) => {
+                if binding.annotated && !compatible(expected, &found) {
+                    return Err(CompileError::new(format!(
+                        "Cannot assign {found} to '{}' of type {expected}",
+                        assignement.name
+                    )));
+                }
+                if !binding.annotated {
+                    binding.value = join(expected, &found);
+                }
+                binding.callable = callable;
+            }
+            ([Accessor::Index(_)], Type::List(item)) => {
+                if binding.annotated && !compatible(item, &found) {
+                    return Err(CompileError::new(format!(
+                        "Cannot assign {found} to an item of '{}' of type {}",
+                        assignement.name, binding.value
+                    )));
+                }
+                if !binding.annotated {
+                    binding.value = Type::List(Box::new(join(item, &found)));
+                }
+            }
+            ([Accessor::Index(_)], Type::Map(key, value)) => {
+                if binding.annotated && !(compatible(key, &indexes[0]) && compatible(value, &found))
+                {
+                    return Err(CompileError::new(format!(
+                        "Cannot assign {found} to the {} key of '{}' of type {}",
+                        indexes[0], assignement.name, binding.value
+                    )));
+                }
+                if !binding.annotated {
+                    binding.value = Type::Map(
+                        Box::new(join(key, &indexes[0])),
+                        Box::new(join(value, &found)),
+                    );
+                }
+            }
+            _ => {
+                if !binding.annotated {
+                    binding.value = Type::Any;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a value returned by the function being checked, it has to match the return type
+    /// the function is annotated with
+    fn returned(&mut self, found: Type) -> Result<(), CompileError> {
+        if let Some(function) = self.functions.last_mut() {
+            if let Some(expected) = &function.returns {
+                if !compatible(expected, &found) {
+                    return Err(CompileError::new(format!(
+                        "Expected a return value of type {expected}, found {found}"
+                    )));
+                }
+            }
+            function.returned.push(found);
+        }
+        Ok(())
+    }
+
+    /// Checks the body of a closure, its return type is inferred from the values it returns when
+    /// it isn't annotated
+    fn closure(&mut self, name: &str, closure: &Closure) -> Result<Signature, CompileError> {
+        let mut parameters = Vec::new();
+        for annotation in closure.types.iter() {
+            if let Some(annotation) = annotation {
+                self.annotation(annotation)?;
+            }
+            parameters.push(annotation.clone().unwrap_or(Type::Any));
+        }
+        if let Some(returns) = &closure.returns {
+            self.annotation(returns)?;
+        }
+
+        self.scopes.push(HashMap::new());
+        for (argument, (annotation, value)) in closure
+            .arguments
+            .iter()
+            .zip(closure.types.iter().zip(parameters.iter()))
+        {
+            self.declare(
+                argument,
+                Binding {
+                    value: value.clone(),
+                    annotated: annotation.is_some(),
+                    callable: None,
+                },
+            );
+        }
+        if let Some(rest) = &closure.rest {
+            self.declare(rest, Binding::inferred(Type::List(Box::new(Type::Any))));
+        }
+
+        self.functions.push(Function {
+            name: name.to_string(),
+            returns: closure.returns.clone(),
+            returned: Vec::new(),
+        });
+        // A call ending the body returns its value
+        let result = match closure.body.body.split_last() {
+            Some((Statement::FunctionCall(call), body)) => self
+                .statements(body)
+                .and_then(|_| self.call(call))
+                .and_then(|found| self.returned(found)),
+            _ => self.statements(&closure.body.body),
+        };
+        self.scopes.pop();
+        // The function stays on the stack on errors, they are reported with its name
+        result?;
+
+        let function = self.functions.pop().unwrap();
+        let returns = match &closure.returns {
+            Some(returns) => returns.clone(),
+            None => function
+                .returned
+                .iter()
+                .skip(1)
+                .fold(function.returned.first().cloned(), |returns, value| {
+                    returns.map(|returns| join(&returns, value))
+                })
+                .unwrap_or(Type::Any),
+        };
+
+        Ok(Signature {
+            parameters,
+            variadic: closure.rest.is_some(),
+            returns,
+        })
+    }
+
+    /// The type of a value, along with its signature when it can be called
+    fn value(&mut self, value: &Statement) -> Result<(Type, Option<Callable>), CompileError> {
+        match value {
+            Statement::Closure(closure) => {
+                let name = match self.functions.last() {
+                    Some(function) => function.name.clone(),
+                    None => "closure".to_string(),
+                };
+                let signature = self.closure(&name, closure)?;
+                Ok((Type::Closure, Some(Callable::Function(signature))))
+            }
+            Statement::Variable(name) => Ok(match self.lookup(name) {
+                Some(binding) => (binding.value.clone(), binding.callable.clone()),
+                None => (Type::Any, None),
+            }),
+            value => Ok((self.expression(value)?, None)),
+        }
+    }
+
+    fn items(&mut self, items: &[Statement]) -> Result<Type, CompileError> {
+        let mut joined: Option<Type> = None;
+
+        for item in items {
+            let item = match item {
+                Statement::Spread(list) => match self.expression(list)? {
+                    Type::List(item) => *item,
+                    _ => Type::Any,
+                },
+                item => self.expression(item)?,
+            };
+
+            joined = Some(match joined {
+                Some(joined) => join(&joined, &item),
+                None => item,
+            });
+        }
+
+        Ok(joined.unwrap_or(Type::Any))
+    }
+
+    fn expression(&mut self, statement: &Statement) -> Result<Type, CompileError> {
+        Ok(match statement {
+            Statement::String(_) => Type::String,
+            Statement::Interpolation(parts) => {
+                for part in parts {
+                    self.expression(part)?;
+                }
+                Type::String
+            }
+            Statement::Number(Number::Int(_)) => Type::Int,
+            Statement::Number(Number::Float(_)) => Type::Float,
+            Statement::Boolean(_) => Type::Boolean,
+            Statement::Variable(_) | Statement::Closure(_) => self.value(statement)?.0,
+            Statement::FunctionCall(call) => self.call(call)?,
+            Statement::Operation(operation) => {
+                let left = self.expression(&operation.left)?;
+                let right = self.expression(&operation.right)?;
+                self::operation(operation.operator, left, right)?
+            }
+            Statement::UnaryOperation(operator, value) => {
+                let value = self.expression(value)?;
+                unary_operation(*operator, value)?
+            }
+            Statement::List(items) => Type::List(Box::new(self.items(items)?)),
+            Statement::Map(entries) => {
+                let mut keys = Vec::new();
+                let mut values = Vec::new();
+                for (key, value) in entries {
+                    keys.push(key.clone());
+                    values.push(value.clone());
+                }
+                Type::Map(Box::new(self.items(&keys)?), Box::new(self.items(&values)?))
+            }
+            Statement::Tuple(items) => {
+                if items
+                    .iter()
+                    .any(|item| matches!(item, Statement::Spread(_)))
+                {
+                    self.items(items)?;
+                    Type::Any
+                } else {
+                    Type::Tuple(
+                        items
+                            .iter()
+                            .map(|item| self.expression(item))
+                            .collect::<Result<Vec<Type>, CompileError>>()?,
+                    )
+                }
+            }
+            Statement::Index(value, index) => {
+                let value = self.expression(value)?;
+                let position = match index.as_ref() {
+                    Statement::Number(Number::Int(position)) => Some(*position),
+                    _ => None,
+                };
+                let index = self.expression(index)?;
+
+                match value {
+                    Type::List(item) => {
+                        if !compatible(&Type::Int, &index) {
+                            return Err(CompileError::new(format!(
+                                "Expected an Int to index a List, found {index}"
+                            )));
+                        }
+                        *item
+                    }
+                    Type::Map(_, value) => *value,
+                    Type::Tuple(items) => position
+                        .and_then(|position| items.get(usize::try_from(position).ok()?).cloned())
+                        .unwrap_or(Type::Any),
+                    _ => Type::Any,
+                }
+            }
+            Statement::Field(record, name) => {
+                // `math.pi` reads the `pi` variable exported by the `math` module
+                if let Statement::Variable(namespace) = record.as_ref() {
+                    if self.lookup(namespace).is_none() {
+                        return Ok(Type::Any);
+                    }
+                }
+
+                if let Type::Named(record) = self.expression(record)? {
+                    if let Some(fields) = self.records.get(&record) {
+                        if !fields.contains(name) {
+                            return Err(CompileError::new(format!(
+                                "Type {record} has no field '{name}'"
+                            )));
+                        }
+                    }
+                }
+                Type::Any
+            }
+            Statement::Spread(list) => {
+                self.expression(list)?;
+                Type::Any
+            }
+            Statement::Try(value) => {
+                let found = self.expression(value)?;
+                let is_failable = match &found {
+                    Type::Named(name) => name == RESULT || name == OPTION,
+                    found => *found == Type::Any,
+                };
+                if !is_failable {
+                    return Err(CompileError::new(format!(
+                        "Expected a Result or an Option before '?', found {found}"
+                    )));
+                }
+
+                // The `Err` or `None` is returned from the function
+                if let Some(function) = self.functions.last_mut() {
+                    if let Some(expected) = &function.returns {
+                        if !compatible(expected, &found) {
+                            return Err(CompileError::new(format!(
+                                "Cannot use '?' on a {found} in a function returning {expected}"
+                            )));
+                        }
+                    }
+                    function.returned.push(found);
+                }
+                Type::Any
+            }
+            Statement::Reference(name) => match self.lookup_mut(name) {
+                // The variable can be modified through the reference, so only the type of an
+                // annotated one stays known
+                Some(binding) if binding.annotated => {
+                    Type::Reference(Box::new(binding.value.clone()))
+                }
+                Some(binding) => {
+                    binding.value = Type::Any;
+                    Type::Reference(Box::new(Type::Any))
+                }
+                None => Type::Reference(Box::new(Type::Any)),
+            },
+            Statement::Dereference(value) => match self.expression(value)? {
+                Type::Reference(value) => *value,
+                Type::Any => Type::Any,
+                found => {
+                    return Err(CompileError::new(format!(
+                        "Expected a Reference after 'deref', found {found}"
+                    )))
+                }
+            },
+            _ => Type::Any,
+        })
+    }
+
+    fn call(&mut self, call: &FunctionCall) -> Result<Type, CompileError> {
+        let arguments = call
+            .arguments
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect::<Result<Vec<Type>, CompileError>>()?;
+
+        // The number of spread arguments is only known at runtime
+        if call
+            .arguments
+            .iter()
+            .any(|argument| matches!(argument, Statement::Spread(_)))
+        {
+            return Ok(Type::Any);
+        }
+
+        let Some(binding) = self.lookup(&call.name).cloned() else {
+            return Ok(Type::Any);
+        };
+
+        match binding.callable {
+            Some(Callable::Builtin) => self.builtin(call, &arguments),
+            Some(Callable::Function(signature)) => {
+                let expected = signature.parameters.len();
+                if arguments.len() < expected || (!signature.variadic && arguments.len() > expected)
+                {
+                    return Err(CompileError::new(format!(
+                        "Function '{}' takes {expected} arguments, found {}",
+                        call.name,
+                        arguments.len()
+                    )));
+                }
+
+                for (index, (expected, found)) in signature
+                    .parameters
+                    .iter()
+                    .zip(arguments.iter())
+                    .enumerate()
+                {
+                    if !compatible(expected, found) {
+                        return Err(CompileError::new(format!(
+                            "Expected {expected} as argument {} of function '{}', found {found}",
+                            index + 1,
+                            call.name
+                        )));
+                    }
+                }
+
+                Ok(signature.returns)
+            }
+            None => match &binding.value {
+                Type::Closure | Type::Any => Ok(Type::Any),
+                value => Err(CompileError::new(format!(
+                    "Cannot call '{}' of type {value}",
+                    call.name
+                ))),
+            },
+        }
+    }
+
+    /// Follows the rules of the native functions, which would fail at runtime otherwise
+    fn builtin(&mut self, call: &FunctionCall, arguments: &[Type]) -> Result<Type, CompileError> {
+        let name = call.name.as_str();
+        let count = |expected: usize| {
+            if arguments.len() == expected {
+                Ok(())
+            } else {
+                Err(CompileError::new(format!(
+                    "Function '{name}' takes {expected} arguments, found {}",
+                    arguments.len()
+                )))
+            }
+        };
+
+        Ok(match name {
+            "add" => {
+                let mut sum: Option<Type> = None;
+                for argument in arguments {
+                    if !is_number(argument) {
+                        return Err(CompileError::new(format!(
+                            "Function 'add' can only add Ints, Floats or BigInts, found {argument}"
+                        )));
+                    }
+                    sum = Some(match sum {
+                        None => argument.clone(),
+                        Some(sum) => operation(Operator::Addition, sum.clone(), argument.clone())
+                            .map_err(|_| {
+                            CompileError::new(format!(
+                                "Function 'add' cannot add {argument} to {sum}"
+                            ))
+                        })?,
+                    });
+                }
+                sum.unwrap_or(Type::Any)
+            }
+            "smaller" => {
+                count(2)?;
+                if let Some(argument) = arguments.iter().find(|argument| !is_number(argument)) {
+                    return Err(CompileError::new(format!(
+                        "Function 'smaller' can only compare Ints, Floats or BigInts, found {argument}"
+                    )));
+                }
+                operation(
+                    Operator::Smaller,
+                    arguments[0].clone(),
+                    arguments[1].clone(),
+                )
+                .map_err(|_| {
+                    CompileError::new(format!(
+                        "Function 'smaller' cannot compare {} and {}",
+                        arguments[0], arguments[1]
+                    ))
+                })?
+            }
+            "equals" => {
+                count(2)?;
+                Type::Boolean
+            }
+            "len" => {
+                count(1)?;
+                match &arguments[0] {
+                    Type::List(_) | Type::Map(..) | Type::String | Type::Any => Type::Int,
+                    found => {
+                        return Err(CompileError::new(format!(
+                            "Expected a List, a Map or a String in function 'len', found {found}"
+                        )))
+                    }
+                }
+            }
+            // Any number of values can be pushed at once
+            "push" => {
+                if arguments.len() < 2 {
+                    return Err(CompileError::new(format!(
+                        "Function 'push' takes at least 2 arguments, found {}",
+                        arguments.len()
+                    )));
+                }
+                if let Statement::Variable(list) = &call.arguments[0] {
+                    if let Some(binding) = self.lookup_mut(list.as_str()) {
+                        for pushed in &arguments[1..] {
+                            let Type::List(item) = &binding.value else {
+                                break;
+                            };
+                            if binding.annotated && !compatible(item, pushed) {
+                                return Err(CompileError::new(format!(
+                                    "Cannot push {pushed} to '{list}' of type {}",
+                                    binding.value
+                                )));
+                            }
+                            if !binding.annotated {
+                                binding.value = Type::List(Box::new(join(item, pushed)));
+                            }
+                        }
+                    }
+                }
+                Type::Any
+            }
+            "pop" => match arguments.first() {
+                Some(Type::List(item)) => *item.clone(),
+                _ => Type::Any,
+            },
+            "keys" => match arguments.first() {
+                Some(Type::Map(key, _)) => Type::List(key.clone()),
+                _ => Type::List(Box::new(Type::Any)),
+            },
+            "values" => match arguments.first() {
+                Some(Type::Map(_, value)) => Type::List(value.clone()),
+                _ => Type::List(Box::new(Type::Any)),
+            },
+            "has" => Type::Boolean,
+            "date" => Type::Int,
+            "big" => {
+                count(1)?;
+                match &arguments[0] {
+                    Type::Int | Type::String | Type::BigInt | Type::Any => Type::BigInt,
+                    found => {
+                        return Err(CompileError::new(format!(
+                            "Expected an Int or a String in function 'big', found {found}"
+                        )))
+                    }
+                }
+            }
+            "input" | "parse_int" | "parse_float" => Type::Named(RESULT.to_string()),
+            "get" => Type::Named(OPTION.to_string()),
+            _ => Type::Any,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsers::program::program;
+
+    fn check(code: &str) -> Result<Checker, CompileError> {
+        let (_, parsed) = program(code).unwrap();
+        let mut checker = Checker::new();
+        checker.check(&parsed.statements.body)?;
+        Ok(checker)
+    }
+
+    #[test]
+    pub fn type_errors() {
+        let errors = [
+            (
+                "main -> let x: Int = \"a\" end",
+                "Cannot assign String to 'x' of type Int",
+            ),
+            (
+                "main -> return \"a\" + 1 end",
+                "Cannot apply '+' to String and Int",
+            ),
+            (
+                "main -> return smaller(true, 1) end",
+                "Function 'smaller' can only compare Ints, Floats or BigInts, found Boolean",
+            ),
+            (
+                "main -> return add(1, 1.5) end",
+                "Function 'add' cannot add Float to Int",
+            ),
+            (
+                "one x: Int -> Int\n return \"a\" end main -> return one(1) end",
+                "Expected a return value of type Int, found String",
+            ),
+            (
+                "one x: Int -> return x end main -> return one(\"a\") end",
+                "Expected Int as argument 1 of function 'one', found String",
+            ),
+            (
+                "double x: Int -> return x * 2 end main -> let s: String = double(2) end",
+                "Cannot assign Int to 's' of type String",
+            ),
+            (
+                "main -> mut items: List[Int] = [] push(items, 1.5) end",
+                "Cannot push Float to 'items' of type List[Int]",
+            ),
+            (
+                "main -> mut items: List[Int] = [] push(items, 1, \"a\") end",
+                "Cannot push String to 'items' of type List[Int]",
+            ),
+            (
+                "main -> mut items = [] push(items) end",
+                "Function 'push' takes at least 2 arguments, found 1",
+            ),
+            (
+                "positive x: Int -> Int\nx > 0\nend",
+                "Expected a return value of type Int, found Boolean",
+            ),
+            ("main -> let p: Pointt = 1 end", "Unknown type 'Pointt'"),
+        ];
+
+        for (code, message) in errors {
+            let error = check(code).err().unwrap();
+            assert!(error.message.starts_with(message), "{}", error.message);
+        }
+    }
+
+    #[test]
+    pub fn numeric_builtins() {
+        // `add` and `smaller` take the numbers `+` and `<` take
+        let code = "main -> return (add(big(1), 1, 2), add(1.5, 2.5), smaller(1, big(2))) end";
+        assert!(check(code).is_ok());
+
+        assert!(check("main -> return add(big(1), 1.5) end").is_err());
+        assert!(check("main -> return smaller(big(1), 1.5) end").is_err());
+    }
+
+    #[test]
+    pub fn declared_types() {
+        let code = "shape x -> let s: shapes.Shape = x end";
+        assert!(check(code).is_err());
+
+        let (_, parsed) = program(code).unwrap();
+        let mut checker = Checker::new();
+        checker.declare_types(vec!["shapes.Shape".to_string()]);
+        assert!(checker.check(&parsed.statements.body).is_ok());
+    }
+
+    #[test]
+    pub fn warnings() {
+        let checker = check("main ->\nparse_int(\"1\")\nreturn 0\nend").unwrap();
+        assert_eq!(checker.warnings.len(), 1);
+
+        // The Result of a call ending a function is returned
+        let checker = check("main -> parse_int(\"1\") end").unwrap();
+        assert!(checker.warnings.is_empty());
+    }
+}
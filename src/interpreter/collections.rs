@@ -0,0 +1,214 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use super::{RuntimeError, Value};
+
+/// A value that can be used as the key of a map: strings and numbers.
+/// Keys of different types are never equal, so `1` and `1.0` are two different keys
+#[derive(Debug, Clone)]
+pub enum Key {
+    String(String),
+    Int64(i64),
+    Float64(f64),
+}
+
+impl Key {
+    pub fn from_value(value: Value) -> Result<Self, RuntimeError> {
+        match value {
+            Value::String(content) => Ok(Key::String(content)),
+            Value::Int64(num) => Ok(Key::Int64(num)),
+            Value::Float64(num) if num.is_nan() => {
+                Err(RuntimeError::new("NaN cannot be used as the key of a Map"))
+            }
+            // -0.0 and 0.0 are equal but have different bits
+            Value::Float64(num) => Ok(Key::Float64(if num == 0.0 { 0.0 } else { num })),
+            value => Err(RuntimeError::new(format!(
+                "Only Strings and numbers can be used as the key of a Map, found {}",
+                value.type_name()
+            ))),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        match self {
+            Key::String(content) => Value::String(content.clone()),
+            Key::Int64(num) => Value::Int64(*num),
+            Key::Float64(num) => Value::Float64(*num),
+        }
+    }
+
+    /// Order of the types when sorting keys: numbers come before strings
+    fn rank(&self) -> u8 {
+        match self {
+            Key::Int64(_) => 0,
+            Key::Float64(_) => 1,
+            Key::String(_) => 2,
+        }
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Key::String(val1), Key::String(val2)) => val1 == val2,
+            (Key::Int64(val1), Key::Int64(val2)) => val1 == val2,
+            // NaN is never a key, so comparing the bits is the same as comparing the numbers
+            (Key::Float64(val1), Key::Float64(val2)) => val1.to_bits() == val2.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Key {}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Key::String(content) => content.hash(state),
+            Key::Int64(num) => num.hash(state),
+            Key::Float64(num) => num.to_bits().hash(state),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Key::String(val1), Key::String(val2)) => val1.cmp(val2),
+            (Key::Int64(val1), Key::Int64(val2)) => val1.cmp(val2),
+            (Key::Float64(val1), Key::Float64(val2)) => val1.total_cmp(val2),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::String(content) => write!(f, "\"{content}\""),
+            Key::Int64(num) => write!(f, "{num}"),
+            Key::Float64(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+/// The entries of a map sorted by key, so that maps are always shown in the same order
+pub fn sorted_entries(entries: &HashMap<Key, Value>) -> Vec<(&Key, &Value)> {
+    let mut entries: Vec<(&Key, &Value)> = entries.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+/// Converts an index to a position in a list, making sure it's in range
+fn position(items: &[Value], index: &Value) -> Result<usize, RuntimeError> {
+    match index {
+        Value::Int64(position) if *position >= 0 && (*position as usize) < items.len() => {
+            Ok(*position as usize)
+        }
+        Value::Int64(position) => Err(RuntimeError::new(format!(
+            "Index {position} is out of range for a collection of length {}",
+            items.len()
+        ))),
+        index => Err(RuntimeError::new(format!(
+            "Expected an Int to index a List, found {}",
+            index.type_name()
+        ))),
+    }
+}
+
+fn missing_key(key: &Key) -> RuntimeError {
+    RuntimeError::new(format!("Key {key} was not found in the Map"))
+}
+
+fn not_indexable(value: &Value) -> RuntimeError {
+    RuntimeError::new(format!(
+        "Cannot index into a value of type {}",
+        value.type_name()
+    ))
+}
+
+pub fn index_ref<'a>(value: &'a Value, index: &Value) -> Result<&'a Value, RuntimeError> {
+    match value {
+        Value::List(items) | Value::Tuple(items) => Ok(&items[position(items, index)?]),
+        Value::Map(entries) => {
+            let key = Key::from_value(index.clone())?;
+            entries.get(&key).ok_or_else(|| missing_key(&key))
+        }
+        value => Err(not_indexable(value)),
+    }
+}
+
+pub fn index_mut<'a>(value: &'a mut Value, index: &Value) -> Result<&'a mut Value, RuntimeError> {
+    match value {
+        Value::List(items) => {
+            let position = position(items, index)?;
+            Ok(&mut items[position])
+        }
+        Value::Map(entries) => {
+            let key = Key::from_value(index.clone())?;
+            match entries.get_mut(&key) {
+                Some(entry) => Ok(entry),
+                None => Err(missing_key(&key)),
+            }
+        }
+        Value::Tuple(_) => Err(RuntimeError::new(
+            "The elements of a Tuple cannot be modified",
+        )),
+        value => Err(not_indexable(value)),
+    }
+}
+
+/// Sets the element at an index, maps get a new entry when the key is missing
+pub fn assign_index(value: &mut Value, index: &Value, element: Value) -> Result<(), RuntimeError> {
+    match value {
+        Value::Map(entries) => {
+            entries.insert(Key::from_value(index.clone())?, element);
+        }
+        value => *index_mut(value, index)? = element,
+    }
+
+    Ok(())
+}
+
+fn missing_field(name: &str, field: &str) -> RuntimeError {
+    RuntimeError::new(format!("Type {name} has no field '{field}'"))
+}
+
+fn not_a_record(value: &Value, field: &str) -> RuntimeError {
+    RuntimeError::new(format!(
+        "Cannot access field '{field}' of a value of type {}",
+        value.type_name()
+    ))
+}
+
+pub fn field_ref<'a>(value: &'a Value, field: &str) -> Result<&'a Value, RuntimeError> {
+    match value {
+        Value::Record { name, fields } => fields
+            .iter()
+            .find(|(key, _)| key == field)
+            .map(|(_, value)| value)
+            .ok_or_else(|| missing_field(name, field)),
+        value => Err(not_a_record(value, field)),
+    }
+}
+
+pub fn field_mut<'a>(value: &'a mut Value, field: &str) -> Result<&'a mut Value, RuntimeError> {
+    match value {
+        Value::Record { name, fields } => match fields.iter_mut().find(|(key, _)| key == field) {
+            Some((_, value)) => Ok(value),
+            None => Err(missing_field(name, field)),
+        },
+        value => Err(not_a_record(value, field)),
+    }
+}
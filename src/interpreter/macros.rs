@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::parsers::{
+    list::Accessor,
+    macros::{MacroCall, MacroDeclaration, MacroRule},
+    operations::Operation,
+    statements::{Statement, Statements},
+};
+
+use super::CompileError;
+
+/// Deepest nesting of macro calls, past it a macro is considered to be expanding itself forever
+const MAX_DEPTH: usize = 64;
+
+/// A transformation of the statement tree, each statement can be replaced by any number of
+/// statements: the ones standing where a single one is expected have to be replaced by one
+trait Rewrite {
+    fn rewrite(&mut self, statement: Statement) -> Result<Vec<Statement>, CompileError>;
+
+    fn rewrite_one(&mut self, statement: Statement) -> Result<Statement, CompileError> {
+        let mut statements = self.rewrite(statement)?;
+        match statements.len() {
+            1 => Ok(statements.pop().unwrap()),
+            _ => Err(CompileError::new("Expected a single value")),
+        }
+    }
+
+    fn rewrite_all(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>, CompileError> {
+        let mut rewritten = Vec::new();
+        for statement in statements {
+            rewritten.extend(self.rewrite(statement)?);
+        }
+        Ok(rewritten)
+    }
+
+    fn rewrite_boxed(&mut self, statement: Statement) -> Result<Box<Statement>, CompileError> {
+        Ok(Box::new(self.rewrite_one(statement)?))
+    }
+
+    fn rewrite_body(&mut self, body: Statements) -> Result<Statements, CompileError> {
+        Ok(Statements {
+            body: self.rewrite_all(body.body)?,
+        })
+    }
+}
+
+/// Rewrites the statements held by a statement, leaving the statement itself as it is
+fn rewrite_children(
+    rewriter: &mut impl Rewrite,
+    statement: Statement,
+) -> Result<Statement, CompileError> {
+    Ok(match statement {
+        Statement::Assignement(mut assignement) => {
+            assignement.value = rewriter.rewrite_boxed(*assignement.value)?;
+            assignement.accessors = assignement
+                .accessors
+                .into_iter()
+                .map(|accessor| match accessor {
+                    Accessor::Index(index) => Ok(Accessor::Index(rewriter.rewrite_one(index)?)),
+                    field => Ok(field),
+                })
+                .collect::<Result<Vec<Accessor>, CompileError>>()?;
+            Statement::Assignement(assignement)
+        }
+        Statement::FunctionCall(mut call) => {
+            call.arguments = rewriter.rewrite_all(call.arguments)?;
+            Statement::FunctionCall(call)
+        }
+        Statement::MacroCall(mut call) => {
+            call.arguments = rewriter.rewrite_all(call.arguments)?;
+            Statement::MacroCall(call)
+        }
+        Statement::FunctionDeclaration(mut declaration) => {
+            declaration.closure.body = rewriter.rewrite_body(declaration.closure.body)?;
+            Statement::FunctionDeclaration(declaration)
+        }
+        Statement::Closure(mut closure) => {
+            closure.body = rewriter.rewrite_body(closure.body)?;
+            Statement::Closure(closure)
+        }
+        Statement::Interpolation(parts) => Statement::Interpolation(rewriter.rewrite_all(parts)?),
+        Statement::Return(value) => Statement::Return(rewriter.rewrite_boxed(*value)?),
+        Statement::Conditional(mut conditional) => {
+            let (condition, body) = *conditional.main_condition;
+            conditional.main_condition = Box::new((
+                rewriter.rewrite_one(condition)?,
+                rewriter.rewrite_body(body)?,
+            ));
+            conditional.alternates = conditional
+                .alternates
+                .into_iter()
+                .map(|(condition, body)| {
+                    Ok((
+                        rewriter.rewrite_one(condition)?,
+                        rewriter.rewrite_body(body)?,
+                    ))
+                })
+                .collect::<Result<Vec<(Statement, Statements)>, CompileError>>()?;
+            conditional.fallback = conditional
+                .fallback
+                .map(|body| rewriter.rewrite_body(body))
+                .transpose()?;
+            Statement::Conditional(conditional)
+        }
+        Statement::Operation(operation) => {
+            let operation = *operation;
+            Statement::Operation(Box::new(Operation {
+                operator: operation.operator,
+                left: rewriter.rewrite_one(operation.left)?,
+                right: rewriter.rewrite_one(operation.right)?,
+            }))
+        }
+        Statement::UnaryOperation(operator, value) => {
+            Statement::UnaryOperation(operator, rewriter.rewrite_boxed(*value)?)
+        }
+        Statement::While(mut while_loop) => {
+            while_loop.condition = rewriter.rewrite_boxed(*while_loop.condition)?;
+            while_loop.body = rewriter.rewrite_body(while_loop.body)?;
+            Statement::While(while_loop)
+        }
+        Statement::List(items) => Statement::List(rewriter.rewrite_all(items)?),
+        Statement::Map(entries) => Statement::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| Ok((rewriter.rewrite_one(key)?, rewriter.rewrite_one(value)?)))
+                .collect::<Result<Vec<(Statement, Statement)>, CompileError>>()?,
+        ),
+        Statement::Tuple(items) => Statement::Tuple(rewriter.rewrite_all(items)?),
+        Statement::Destructuring(mut destructuring) => {
+            destructuring.value = rewriter.rewrite_boxed(*destructuring.value)?;
+            Statement::Destructuring(destructuring)
+        }
+        Statement::Index(list, index) => Statement::Index(
+            rewriter.rewrite_boxed(*list)?,
+            rewriter.rewrite_boxed(*index)?,
+        ),
+        Statement::Field(record, name) => Statement::Field(rewriter.rewrite_boxed(*record)?, name),
+        Statement::Match(mut statement) => {
+            statement.value = rewriter.rewrite_boxed(*statement.value)?;
+            for arm in statement.arms.iter_mut() {
+                arm.body = rewriter.rewrite_body(std::mem::replace(
+                    &mut arm.body,
+                    Statements { body: Vec::new() },
+                ))?;
+            }
+            Statement::Match(statement)
+        }
+        Statement::Spread(list) => Statement::Spread(rewriter.rewrite_boxed(*list)?),
+        Statement::Try(value) => Statement::Try(rewriter.rewrite_boxed(*value)?),
+        Statement::Dereference(value) => Statement::Dereference(rewriter.rewrite_boxed(*value)?),
+        Statement::Export(declaration) => Statement::Export(rewriter.rewrite_boxed(*declaration)?),
+        statement => statement,
+    })
+}
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Collects the names declared by the body of a macro rule
+#[derive(Default)]
+struct Declarations {
+    names: Vec<String>,
+}
+
+impl Rewrite for Declarations {
+    fn rewrite(&mut self, statement: Statement) -> Result<Vec<Statement>, CompileError> {
+        match &statement {
+            Statement::Assignement(assignement) if assignement.declaration => {
+                self.names.push(assignement.name.clone())
+            }
+            Statement::Destructuring(destructuring) => {
+                self.names.extend(destructuring.names.iter().cloned())
+            }
+            Statement::FunctionDeclaration(declaration) => {
+                self.names.push(declaration.name.clone());
+                self.names
+                    .extend(declaration.closure.arguments.iter().cloned());
+                self.names.extend(declaration.closure.rest.iter().cloned());
+            }
+            Statement::Closure(closure) => {
+                self.names.extend(closure.arguments.iter().cloned());
+                self.names.extend(closure.rest.iter().cloned());
+            }
+            Statement::Match(statement) => {
+                for arm in statement.arms.iter() {
+                    self.names.extend(arm.names.iter().cloned());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(vec![rewrite_children(self, statement)?])
+    }
+}
+
+/// The body of a macro rule instantiated for a call: its parameters are replaced by the arguments
+/// of the call, and the names it declares are renamed so that they can't clash with the names
+/// used around the call
+struct Template<'a> {
+    macro_name: &'a str,
+    arguments: HashMap<String, Statement>,
+    /// The rest parameter of the rule along with the extra arguments of the call
+    rest: Option<(String, Vec<Statement>)>,
+    renamed: HashMap<String, String>,
+}
+
+impl Template<'_> {
+    /// The name a name of the body refers to once instantiated, parameters have to be replaced
+    /// by a variable to be called, assigned to or referenced
+    fn name(&self, name: String) -> Result<String, CompileError> {
+        if let Some(renamed) = self.renamed.get(&name) {
+            return Ok(renamed.clone());
+        }
+
+        match self.arguments.get(&name) {
+            Some(Statement::Variable(variable)) => Ok(variable.clone()),
+            Some(_) => Err(CompileError::new(format!(
+                "Argument '{name}' of macro '{}' has to be a variable to be used as one",
+                self.macro_name
+            ))),
+            None => Ok(name),
+        }
+    }
+
+    fn rename(&self, name: &mut String) {
+        if let Some(renamed) = self.renamed.get(name) {
+            *name = renamed.clone();
+        }
+    }
+}
+
+impl Rewrite for Template<'_> {
+    fn rewrite(&mut self, statement: Statement) -> Result<Vec<Statement>, CompileError> {
+        let statement = match statement {
+            Statement::Variable(name) => {
+                if let Some(argument) = self.arguments.get(&name) {
+                    return Ok(vec![argument.clone()]);
+                }
+                match &self.rest {
+                    Some((rest, arguments)) if *rest == name => Statement::List(arguments.clone()),
+                    _ => Statement::Variable(self.name(name)?),
+                }
+            }
+            Statement::Spread(list) => match (*list, &self.rest) {
+                (Statement::Variable(name), Some((rest, arguments))) if name == *rest => {
+                    return Ok(arguments.clone());
+                }
+                (list, _) => Statement::Spread(Box::new(list)),
+            },
+            Statement::FunctionCall(mut call) => {
+                call.name = self.name(call.name)?;
+                Statement::FunctionCall(call)
+            }
+            Statement::Reference(name) => Statement::Reference(self.name(name)?),
+            Statement::Assignement(mut assignement) => {
+                assignement.name = self.name(assignement.name)?;
+                if assignement.declaration {
+                    assignement.id = Some(new_id());
+                }
+                Statement::Assignement(assignement)
+            }
+            Statement::Destructuring(mut destructuring) => {
+                destructuring
+                    .names
+                    .iter_mut()
+                    .for_each(|name| self.rename(name));
+                destructuring.ids = destructuring.names.iter().map(|_| new_id()).collect();
+                Statement::Destructuring(destructuring)
+            }
+            Statement::FunctionDeclaration(mut declaration) => {
+                self.rename(&mut declaration.name);
+                declaration.id = new_id();
+                declaration.closure.id = new_id();
+                let closure = &mut declaration.closure;
+                closure
+                    .arguments
+                    .iter_mut()
+                    .for_each(|name| self.rename(name));
+                closure.rest.iter_mut().for_each(|name| self.rename(name));
+                Statement::FunctionDeclaration(declaration)
+            }
+            Statement::Closure(mut closure) => {
+                closure.id = new_id();
+                closure
+                    .arguments
+                    .iter_mut()
+                    .for_each(|name| self.rename(name));
+                closure.rest.iter_mut().for_each(|name| self.rename(name));
+                Statement::Closure(closure)
+            }
+            Statement::Match(mut statement) => {
+                for arm in statement.arms.iter_mut() {
+                    arm.names.iter_mut().for_each(|name| self.rename(name));
+                    arm.ids = arm.names.iter().map(|_| new_id()).collect();
+                }
+                Statement::Match(statement)
+            }
+            statement => statement,
+        };
+
+        Ok(vec![rewrite_children(self, statement)?])
+    }
+}
+
+/// Replaces the macro calls of a file by the expansion of the macros it declares
+struct Expander<'a> {
+    /// The source of the file, to locate the calls in errors
+    code: &'a str,
+    macros: HashMap<String, MacroDeclaration>,
+    /// The number of calls being expanded inside of each other
+    depth: usize,
+    /// The number of calls expanded so far, it makes the names declared by each expansion unique
+    expansions: usize,
+}
+
+impl Expander<'_> {
+    /// Points an error to the call it comes from. Errors of nested calls point to the outermost
+    /// one, as the nested ones are not in the source of the program
+    fn locate(&self, error: CompileError, call: &str, position: usize) -> CompileError {
+        if self.depth > 0 {
+            return error;
+        }
+
+        let offset = self.code.len() - position;
+        let before = &self.code[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+
+        CompileError::new(format!(
+            "{} (in the call to {call}! at line {line}, column {column})",
+            error.message
+        ))
+    }
+
+    fn rule(declaration: &MacroDeclaration, arguments: usize) -> Result<&MacroRule, CompileError> {
+        declaration
+            .rules
+            .iter()
+            .find(|rule| match rule.rest {
+                Some(_) => arguments >= rule.parameters.len(),
+                None => arguments == rule.parameters.len(),
+            })
+            .ok_or_else(|| {
+                CompileError::new(format!(
+                    "No rule of macro '{}' takes {arguments} arguments",
+                    declaration.name
+                ))
+            })
+    }
+
+    fn expand(&mut self, call: MacroCall) -> Result<Vec<Statement>, CompileError> {
+        let declaration = self
+            .macros
+            .get(&call.name)
+            .cloned()
+            .ok_or_else(|| CompileError::new(format!("Unknown macro '{}'", call.name)))?;
+
+        if self.depth == MAX_DEPTH {
+            return Err(CompileError::new(format!(
+                "The expansion of macro '{}' is too deep, it may be expanding itself forever",
+                call.name
+            )));
+        }
+
+        let mut arguments = self.rewrite_all(call.arguments)?;
+        if arguments
+            .iter()
+            .any(|argument| matches!(argument, Statement::Spread(_)))
+        {
+            return Err(CompileError::new(format!(
+                "Cannot spread a list into the arguments of macro '{}', they must be known",
+                call.name
+            )));
+        }
+
+        let rule = Self::rule(&declaration, arguments.len())?;
+        let extra = arguments.split_off(rule.parameters.len());
+
+        let mut declarations = Declarations::default();
+        declarations.rewrite_all(rule.body.clone())?;
+
+        self.expansions += 1;
+        let renamed = declarations
+            .names
+            .into_iter()
+            .map(|name| {
+                let unique = format!("{name}!{}", self.expansions);
+                (name, unique)
+            })
+            .collect();
+
+        let mut template = Template {
+            macro_name: &declaration.name,
+            arguments: rule.parameters.iter().cloned().zip(arguments).collect(),
+            rest: rule.rest.clone().map(|rest| (rest, extra)),
+            renamed,
+        };
+        let body = template.rewrite_all(rule.body.clone())?;
+
+        self.depth += 1;
+        let expansion = self.rewrite_all(body);
+        self.depth -= 1;
+
+        expansion
+    }
+}
+
+impl Rewrite for Expander<'_> {
+    fn rewrite(&mut self, statement: Statement) -> Result<Vec<Statement>, CompileError> {
+        match statement {
+            Statement::MacroCall(call) => {
+                let (name, position) = (call.name.clone(), call.position);
+                self.expand(call)
+                    .map_err(|error| self.locate(error, &name, position))
+            }
+            Statement::MacroDeclaration(declaration) => Err(CompileError::new(format!(
+                "Macro '{}' has to be declared at the top level of a file",
+                declaration.name
+            ))),
+            statement => Ok(vec![rewrite_children(self, statement)?]),
+        }
+    }
+
+    fn rewrite_one(&mut self, statement: Statement) -> Result<Statement, CompileError> {
+        let Statement::MacroCall(call) = &statement else {
+            return Ok(self.rewrite(statement)?.pop().unwrap());
+        };
+
+        let (name, position) = (call.name.clone(), call.position);
+        let mut statements = self.rewrite(statement)?;
+
+        match statements.len() {
+            1 => Ok(statements.pop().unwrap()),
+            _ => Err(self.locate(
+                CompileError::new(format!(
+                    "Macro '{name}' has to expand to a single value to be used as one"
+                )),
+                &name,
+                position,
+            )),
+        }
+    }
+}
+
+/// Expands the macro calls of a file, the macros it declares can be used anywhere in it
+pub fn expand(statements: Statements, code: &str) -> Result<Statements, CompileError> {
+    let mut macros = HashMap::new();
+    let mut body = Vec::new();
+
+    for statement in statements.body {
+        match statement {
+            Statement::MacroDeclaration(declaration) => {
+                let name = declaration.name.clone();
+                if macros.insert(name.clone(), declaration).is_some() {
+                    return Err(CompileError::new(format!(
+                        "Macro '{name}' is declared twice"
+                    )));
+                }
+            }
+            statement => body.push(statement),
+        }
+    }
+
+    let mut expander = Expander {
+        code,
+        macros,
+        depth: 0,
+        expansions: 0,
+    };
+
+    Ok(Statements {
+        body: expander.rewrite_all(body)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsers::program::program;
+
+    fn expanded(code: &str) -> Result<Statements, CompileError> {
+        let (_, parsed) = program(code).unwrap();
+        expand(parsed.statements, code)
+    }
+
+    #[test]
+    pub fn hygiene() {
+        let code = "
+        macro swap (a, b) ->
+            let tmp = a
+            a = b
+            b = tmp
+        end end
+
+        main ->
+            mut tmp = 1
+            mut other = 2
+            swap!(tmp, other)
+        end
+        ";
+        let statements = expanded(code).unwrap();
+        let [Statement::FunctionDeclaration(main)] = statements.body.as_slice() else {
+            panic!("the macro declaration is not removed");
+        };
+
+        // The variable declared by the expansion doesn't shadow the one it swaps
+        let names: Vec<(&str, bool)> = main
+            .closure
+            .body
+            .body
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Assignement(assignement) => {
+                    Some((assignement.name.as_str(), assignement.declaration))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("tmp", true),
+                ("other", true),
+                ("tmp!1", true),
+                ("tmp", false),
+                ("other", false)
+            ]
+        );
+    }
+
+    #[test]
+    pub fn expansion_errors() {
+        let errors = [
+            (
+                "macro pair (a, b) -> (a, b) end end\nmain ->\n    return pair!(1)\nend",
+                "No rule of macro 'pair' takes 1 arguments (in the call to pair! at line 3, column 12)",
+            ),
+            (
+                "macro forever (a) -> forever!(a) end end\nmain -> return forever!(1) end",
+                "The expansion of macro 'forever' is too deep",
+            ),
+            ("main -> return unknown!(1) end", "Unknown macro 'unknown'"),
+            (
+                "macro one () -> 1 end end\nmacro one () -> 2 end end",
+                "Macro 'one' is declared twice",
+            ),
+            (
+                "main ->\nmacro one () -> 1 end end\nend",
+                "Macro 'one' has to be declared at the top level of a file",
+            ),
+            (
+                "macro id (a) -> a end end\nmain -> let xs = [1] return id!(...xs) end",
+                "Cannot spread a list into the arguments of macro 'id'",
+            ),
+        ];
+
+        for (code, message) in errors {
+            let error = expanded(code).unwrap_err();
+            assert!(error.message.starts_with(message), "{}", error.message);
+        }
+    }
+}
@@ -1,36 +1,92 @@
+mod builtins;
+mod checker;
+mod collections;
+mod macros;
+mod operations;
+
 use colored::*;
 use std::{
-    cell::Ref,
+    cell::RefCell,
     collections::HashMap,
-    fmt, io,
+    fmt, fs,
     ops::Deref,
-    time::{Instant, SystemTime, UNIX_EPOCH},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
 };
 
 use dashmap::DashMap;
 use nom::{error::convert_error, Finish};
+use num_bigint::BigInt;
 
 use crate::parsers::{
+    closure::Closure,
     conditional::Conditional,
+    enumeration::{EnumDeclaration, Variant},
     function_declaration::FunctionDeclaration,
+    list::Accessor,
+    match_statement::Match,
     number::Number,
-    program::program,
+    operations::{Operator, UnaryOperator},
+    program::{program, Program},
+    record::TypeDeclaration,
     statements::{Statement, Statements},
+    tuple::Destructuring,
     variable::Assignement,
+    while_loop::WhileLoop,
+};
+
+use self::builtins::*;
+use self::checker::Checker;
+use self::collections::{
+    assign_index, field_mut, field_ref, index_mut, index_ref, sorted_entries, Key,
 };
+use self::macros::expand;
+use self::operations::{binary_operation, unary_operation};
 
 #[derive(Debug, Clone)]
 
 pub enum Value {
     String(String),
-    Int32(i32),
-    Float32(f32),
+    Int64(i64),
+    Float64(f64),
+    /// An integer without size limit, created with `big(value)`
+    BigInt(BigInt),
     Closure {
-        argument_count: u8,
+        /// The memory slots the arguments are stored in when the closure is called, native
+        /// functions have none and receive their arguments directly
+        arguments: Vec<Id>,
+        /// The memory slot collecting the extra arguments into a list, for variadic closures
+        rest: Option<Id>,
         instructions: Vec<Instruction>,
+        /// The variables of the enclosing functions used by the closure, as they were when it was
+        /// created. Mutable variables are shared with the function that declared them
+        environment: HashMap<Id, Binding>,
+        /// The memory slot of a named function declared inside of another function, it is bound
+        /// to the closure itself when it is called so that it can call itself
+        name: Option<Id>,
     },
-    CopyVar(Id, Box<Option<Value>>),
+    CopyVar(Id),
     Boolean(bool),
+    List(Vec<Value>),
+    Map(HashMap<Key, Value>),
+    Tuple(Vec<Value>),
+    /// An instance of a type declared with `type`, holding the name of the type and its fields in
+    /// the order they were declared
+    Record {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    /// A variant of a sum type, holding the name of the type, the name of the variant and the
+    /// values it carries
+    Variant {
+        enumeration: String,
+        variant: String,
+        values: Vec<Value>,
+    },
+    /// A reference to the memory slot of a variable. The slot lives as long as a reference to it
+    /// does, and can only be modified through the reference if the variable is mutable
+    Reference(Binding),
     /// Field used for instructions that need to be evaluated (operations, function calls) but that
     /// are still considered values in their compiled context
     LazyEval(Box<Instruction>),
@@ -43,13 +99,23 @@ impl PartialEq for Value {
                 Value::String(val2) => val1 == val2,
                 _ => false,
             },
-            Value::Int32(val1) => match other {
-                Value::Int32(val2) => val1 == val2,
+            // Numbers that can be compared with each other are equal when they have the same value
+            Value::Int64(val1) => match other {
+                Value::Int64(val2) => val1 == val2,
+                Value::Float64(val2) => *val1 as f64 == *val2,
+                Value::BigInt(val2) => BigInt::from(*val1) == *val2,
+                _ => false,
+            },
+
+            Value::Float64(val1) => match other {
+                Value::Float64(val2) => val1 == val2,
+                Value::Int64(val2) => *val1 == *val2 as f64,
                 _ => false,
             },
 
-            Value::Float32(val1) => match other {
-                Value::Float32(val2) => val1 == val2,
+            Value::BigInt(val1) => match other {
+                Value::BigInt(val2) => val1 == val2,
+                Value::Int64(val2) => *val1 == BigInt::from(*val2),
                 _ => false,
             },
 
@@ -57,12 +123,115 @@ impl PartialEq for Value {
                 Value::Boolean(val2) => val1 == val2,
                 _ => false,
             },
-            _ => {
-                println!(
-                    "Runtime Error: Did not expect this kind of value in equals, must be an error"
-                );
-                panic!()
+
+            Value::List(val1) => match other {
+                Value::List(val2) => val1 == val2,
+                _ => false,
+            },
+
+            Value::Map(val1) => match other {
+                Value::Map(val2) => val1 == val2,
+                _ => false,
+            },
+
+            Value::Tuple(val1) => match other {
+                Value::Tuple(val2) => val1 == val2,
+                _ => false,
+            },
+
+            Value::Record {
+                name: name1,
+                fields: fields1,
+            } => match other {
+                Value::Record {
+                    name: name2,
+                    fields: fields2,
+                } => name1 == name2 && fields1 == fields2,
+                _ => false,
+            },
+
+            Value::Variant {
+                enumeration: enumeration1,
+                variant: variant1,
+                values: values1,
+            } => match other {
+                Value::Variant {
+                    enumeration: enumeration2,
+                    variant: variant2,
+                    values: values2,
+                } => enumeration1 == enumeration2 && variant1 == variant2 && values1 == values2,
+                _ => false,
+            },
+
+            // References are equal when the values they point to are
+            Value::Reference(target1) => match other {
+                Value::Reference(target2) => {
+                    Rc::ptr_eq(target1, target2) || target1.borrow().value == target2.borrow().value
+                }
+                _ => false,
+            },
+            // Closures are never equal, `Value::equals` reports comparing them as an error
+            Value::Closure { .. } | Value::CopyVar(_) | Value::LazyEval(_) => false,
+        }
+    }
+}
+
+impl Value {
+    /// Compares two values, closures can't be compared, even inside of other values
+    pub fn equals(&self, other: &Value) -> Result<bool, RuntimeError> {
+        for value in [self, other] {
+            if let Value::Closure { .. } = value {
+                return Err(RuntimeError::new("Closures cannot be compared"));
+            }
+            if value.holds_closure() {
+                return Err(RuntimeError::new(format!(
+                    "Cannot compare {} values holding a closure",
+                    value.type_name()
+                )));
+            }
+        }
+        Ok(self == other)
+    }
+
+    /// Wether a closure is found in the value, at any depth
+    fn holds_closure(&self) -> bool {
+        match self {
+            Value::Closure { .. } | Value::CopyVar(_) | Value::LazyEval(_) => true,
+            Value::List(items) | Value::Tuple(items) | Value::Variant { values: items, .. } => {
+                items.iter().any(Value::holds_closure)
             }
+            Value::Map(entries) => entries.values().any(Value::holds_closure),
+            Value::Record { fields, .. } => fields.iter().any(|(_, value)| value.holds_closure()),
+            Value::Reference(target) => target.borrow().value.holds_closure(),
+            _ => false,
+        }
+    }
+
+    /// Name of the type of the value, used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "String",
+            Value::Int64(_) => "Int",
+            Value::Float64(_) => "Float",
+            Value::BigInt(_) => "BigInt",
+            Value::Closure { .. } => "Closure",
+            Value::Boolean(_) => "Boolean",
+            Value::List(_) => "List",
+            Value::Map(_) => "Map",
+            Value::Tuple(_) => "Tuple",
+            Value::Record { .. } => "Record",
+            Value::Variant { .. } => "Enum",
+            Value::Reference(_) => "Reference",
+            Value::CopyVar(..) | Value::LazyEval(_) => "Unevaluated",
+        }
+    }
+
+    /// Formats a value shown inside of a collection, where strings are quoted
+    fn fmt_nested(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(content) => write!(f, "\"{content}\""),
+            Value::Reference(target) => target.borrow().value.fmt_nested(f),
+            value => write!(f, "{value}"),
         }
     }
 }
@@ -72,180 +241,488 @@ impl fmt::Display for Value {
         match self {
             Value::Boolean(value) => write!(f, "{value}"),
             Value::String(content) => write!(f, "{content}"),
-            Value::Float32(num) => write!(f, "{num}"),
-            Value::Int32(num) => write!(f, "{num}"),
+            Value::Float64(num) => write!(f, "{num}"),
+            Value::Int64(num) => write!(f, "{num}"),
+            Value::BigInt(num) => write!(f, "{num}"),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Tuple(items) => {
+                write!(f, "(")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.fmt_nested(f)?;
+                }
+                if items.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Value::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in sorted_entries(entries).into_iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: ")?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Record { name, fields } => {
+                write!(f, "{name}")?;
+                if fields.is_empty() {
+                    return Ok(());
+                }
+                write!(f, " {{ ")?;
+                for (index, (field, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{field}: ")?;
+                    value.fmt_nested(f)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Variant {
+                variant, values, ..
+            } => {
+                write!(f, "{variant}")?;
+                if values.is_empty() {
+                    return Ok(());
+                }
+                write!(f, "(")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.fmt_nested(f)?;
+                }
+                write!(f, ")")
+            }
             Value::Closure { instructions, .. } => {
                 write!(
                     f,
                     "Closure ({})",
-                    match instructions.first().unwrap() {
-                        Instruction::ExtCall(x) => format!("ExtCall {:?}", x),
+                    match instructions.first() {
+                        Some(Instruction::ExtCall(x)) => format!("ExtCall {:?}", x),
+                        Some(Instruction::ExtMutCall(x)) => format!("ExtMutCall {:?}", x),
                         _ => format!("{} instructions", instructions.len()),
                     }
                 )
             }
+            Value::Reference(target) => write!(f, "{}", target.borrow().value),
             //Value::CopyVar(id, val) => write!(f, "{id}"),
             _ => Ok(()),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// An error raised by the engine while executing a program, it stops the execution
+pub struct RuntimeError {
+    pub message: String,
+    /// The `Err` or `None` a `?` was applied to, it returns from the function being executed
+    /// instead of stopping the execution
+    pub propagated: Option<Box<Value>>,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            propagated: None,
+        }
+    }
+
+    fn propagate(value: Value) -> Self {
+        Self {
+            message: format!("Cannot propagate {value} outside of a function"),
+            propagated: Some(Box::new(value)),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Runtime Error: {}", self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An error found while parsing or compiling a program, nothing gets executed
+pub struct CompileError {
+    pub message: String,
+}
+
+impl CompileError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Compile Error: {}", self.message)
+    }
+}
+
 #[derive(Debug, Clone)]
-struct Entry {
+pub struct Entry {
     mutable: bool,
     value: Value,
 }
 
+/// A memory slot, shared between the function that declared it and the closures capturing it
+type Binding = Rc<RefCell<Entry>>;
+
+/// The memory of a function call: its arguments, its local variables and the variables captured
+/// by the closure being called
+type Frame = HashMap<Id, Binding>;
+
 type Id = String;
 
+/// A sum type, with the name and the number of values of each of its variants
+type Enumeration = (String, Vec<(String, usize)>);
+
 #[derive(Clone, Debug)]
 /// An enum containing all the instructions the Engine can execute
 pub enum Instruction {
-    Test(String),
     /// A variable declaration taking its ID, wether it is mutable or not and the default value.
     VariableDeclaration(Id, bool, Value),
     VariableAssignement(Id, Value),
-    PopVariable(Id),
     ExecuteFunction(Id, Vec<Value>),
-    ExtCall(fn(Vec<Value>) -> Option<Value>),
+    ExtCall(fn(Vec<Value>) -> Result<Option<Value>, RuntimeError>),
+    /// A native function that modifies its first argument, which has to be a mutable variable
+    ExtMutCall(fn(&mut Value, Vec<Value>) -> Result<Option<Value>, RuntimeError>),
     Return(Value),
     Conditional(ConditionalInstruction),
+    /// A binary operation between two values, the result of an expression such as `a + b`
+    Operation(Operator, Value, Value),
+    UnaryOperation(UnaryOperator, Value),
+    Loop(LoopInstruction),
+    Break,
+    Continue,
+    /// Creates a list from the values of its items
+    BuildList(Vec<Value>),
+    /// Creates a map from the values of its keys and values
+    BuildMap(Vec<(Value, Value)>),
+    BuildTuple(Vec<Value>),
+    /// Declares one variable per element of a tuple, taking their IDs, wether they are mutable and
+    /// the tuple
+    Destructure(Vec<Id>, bool, Value),
+    /// Reads the element of a list at an index, or the value of a map at a key
+    Index(Value, Value),
+    /// Assigns a value to an element of the list, map or record stored in a variable, taking the
+    /// id of the variable and the accesses leading to the element
+    IndexAssignement(Id, Vec<Access>, Value),
+    /// Creates a record from the name of its type and the values of its fields
+    BuildRecord(String, Vec<(String, Value)>),
+    /// Reads the field of a record
+    Field(Value, String),
+    /// Creates a variant of a sum type from the name of the type, the name of the variant and the
+    /// values it carries
+    BuildVariant(String, String, Vec<Value>),
+    Match(MatchInstruction),
+    /// Creates a string by joining the textual representations of values
+    Concat(Vec<Value>),
+    /// A list whose elements are inserted in the arguments of a call or the items of a collection
+    Spread(Value),
+    /// Creates a closure, capturing the variables it uses from the enclosing functions
+    MakeClosure(ClosureInstruction),
+    /// Unwraps an `Ok` or a `Some`, or returns an `Err` or a `None` from the function: `value?`
+    Propagate(Value),
+    /// Creates a reference to the memory slot of a variable: `ref x`
+    Reference(Id),
+    /// Reads the value a reference points to: `deref r`
+    Dereference(Value),
+    /// Assigns a value to the variable the reference stored in a variable points to, or to one of
+    /// its elements, taking the id of the variable holding the reference
+    DerefAssignement(Id, Vec<Access>, Value),
+    /// A call ending a function, the frame of the function is freed before the callee runs.
+    /// Takes wether the call is returned explicitly, which requires it to produce a value. The
+    /// value of a call ending a function otherwise is returned when there is one
+    TailCall(Id, Vec<Value>, bool),
+}
+
+#[derive(Debug, Clone)]
+/// A step towards the element of a collection being assigned to
+pub enum Access {
+    Index(Value),
+    Field(String),
 }
 
 #[derive(Debug, Clone)]
-struct ConditionalInstruction {
+pub struct ConditionalInstruction {
     main: (Value, Vec<Instruction>),
-    alternates: Option<Vec<(Value, Instruction)>>,
+    /// The `else if` branches, tried in order when the main condition is false
+    alternates: Vec<(Value, Vec<Instruction>)>,
     fallback: Option<Vec<Instruction>>,
 }
 
-fn say_time(name: &str, instant: Instant) {
-    let fmt_str = format!(
-        "✅ Task finished > {} done in: {}s",
-        name,
-        instant.elapsed().as_secs_f32().to_string()
-    );
-    println!("{}", fmt_str.green());
+#[derive(Debug, Clone)]
+pub struct ClosureInstruction {
+    arguments: Vec<Id>,
+    rest: Option<Id>,
+    instructions: Vec<Instruction>,
+    captures: Vec<Id>,
+    name: Option<Id>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchInstruction {
+    value: Value,
+    /// The sum type being matched, unknown when the match only has a `_` arm
+    enumeration: Option<String>,
+    /// The variant handled by each arm (`None` for `_`), the ids its values are bound to and its
+    /// body
+    arms: Vec<(Option<String>, Vec<Id>, Vec<Instruction>)>,
 }
 
-/// An enum used for variable scoping, tells the compiler wether this is a reference
-/// to an argument or a memory slot (id)
 #[derive(Debug, Clone)]
-enum ReferenceType {
-    Id(Id),
-    /// Says that it's an argument, stores the Id of the function it belongs to, and the index of
-    /// the argument
-    Argument(Id, u8),
+pub struct LoopInstruction {
+    condition: Value,
+    body: Vec<Instruction>,
+    /// The memory slots declared in the body, they are freed at the end of each iteration
+    locals: Vec<Id>,
 }
 
-#[derive(Debug)]
-/// A struct that is responsible for parsing a program and generating a list of instructions that
-/// will be fed to the engine
-pub struct Compiler<'a> {
-    scope: &'a mut Vec<HashMap<String, (Id, bool)>>,
-    pub instructions: Vec<Instruction>,
+/// Tells the engine how to continue after executing a list of instructions
+enum Flow {
+    /// Every instruction was executed, carry on with the next ones
+    Next,
+    Return(Value),
+    Break,
+    Continue,
+    /// The function ends by calling a closure, which runs in the given frame once the one of the
+    /// function is freed
+    TailCall(Frame, Vec<Instruction>),
 }
 
-const STD_PREFIX: &str = "msq_std::";
-const PRINT_ID: &str = "msq_std::print()";
-const ADD_ID: &str = "msq_std::add()";
-const EQUALS_ID: &str = "msq_std::equals()";
-const SMALLER_ID: &str = "msq_std::smaller()";
-const DATE_ID: &str = "msq_std::date()";
-const INPUT_ID: &str = "msq_std::input()";
+/// A call to a function, ready to be executed
+enum Call {
+    /// Native functions are called as soon as their arguments are evaluated, this is their result
+    Native(Option<Value>),
+    Closure(Frame, Vec<Instruction>),
+}
 
-fn print(args: Vec<Value>) -> Option<Value> {
-    for arg in args {
-        print!("{}", arg);
+/// Parses a program, turning a syntax error into a compile error pointing at it
+fn parse(code: &str) -> Result<Program, CompileError> {
+    match program(code).finish() {
+        Ok((_, program)) => Ok(program),
+        Err(error) => Err(CompileError::new(convert_error(code, error))),
     }
-    println!("");
-    None
 }
 
-fn date(args: Vec<Value>) -> Option<Value> {
-    Some(Value::Int32(
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i32,
-    ))
+fn canonicalize(path: &Path) -> Result<PathBuf, CompileError> {
+    path.canonicalize()
+        .map_err(|error| CompileError::new(format!("Cannot find file {}: {error}", path.display())))
 }
 
-fn smaller(args: Vec<Value>) -> Option<Value> {
-    if args.len() != 2 {
-        println!("Runtime error: Expected two arguments for function 'smaller'");
-        panic!()
-    }
+fn read_source(path: &Path) -> Result<String, CompileError> {
+    fs::read_to_string(path)
+        .map_err(|error| CompileError::new(format!("Cannot read file {}: {error}", path.display())))
+}
 
-    match args.first().unwrap() {
-        Value::Int32(value1) => match args.last().unwrap() {
-            Value::Float32(value2) => return Some(Value::Boolean(value1 < &(*value2 as i32))),
-            Value::Int32(value2) => return Some(Value::Boolean(value1 < value2)),
-            _ => {
-                panic!()
-            }
-        },
-        Value::Float32(value1) => match args.last().unwrap() {
-            Value::Float32(value2) => return Some(Value::Boolean(value1 < value2)),
-            Value::Int32(value2) => return Some(Value::Boolean(value1 < &(*value2 as f32))),
-            _ => {
-                panic!()
+/// The file of the module imported by `import a.b`, from the directory of the importing file
+fn module_path(directory: &Path, segments: &[String]) -> Result<PathBuf, CompileError> {
+    let mut path = directory.to_path_buf();
+    path.extend(segments);
+    path.set_extension("msq");
+    canonicalize(&path)
+}
+
+/// The types exported by the modules a file imports, under the namespace of their module so that
+/// they can be used in annotations: `shapes.Shape`
+fn imported_types(directory: &Path, statements: &Statements) -> Result<Vec<String>, CompileError> {
+    let mut types = Vec::new();
+
+    for statement in statements.body.iter() {
+        let Statement::Import(segments) = statement else {
+            continue;
+        };
+        let path = module_path(directory, segments)?;
+        let module = parse(&read_source(&path)?).map_err(|error| {
+            CompileError::new(format!("In {}:\n{}", path.display(), error.message))
+        })?;
+        let namespace = segments.last().unwrap();
+
+        for statement in module.statements.body {
+            let Statement::Export(declaration) = statement else {
+                continue;
+            };
+            match *declaration {
+                Statement::TypeDeclaration(declaration) => {
+                    types.push(format!("{namespace}.{}", declaration.name))
+                }
+                Statement::EnumDeclaration(declaration) => {
+                    types.push(format!("{namespace}.{}", declaration.name))
+                }
+                _ => {}
             }
-        },
-        _ => {
-            panic!()
         }
     }
+
+    Ok(types)
 }
 
-fn input(args: Vec<Value>) -> Option<Value> {
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Runtime error: Unable to read user input");
+/// A closure taking one argument per field of a type, `build` creates a value out of them
+fn constructor(id: &str, fields: usize, build: impl FnOnce(Vec<Value>) -> Instruction) -> Value {
+    let arguments: Vec<Id> = (0..fields).map(|index| format!("{id}-{index}")).collect();
+    let values = arguments.iter().cloned().map(Value::CopyVar).collect();
+
+    Value::Closure {
+        arguments,
+        rest: None,
+        instructions: vec![Instruction::Return(Value::LazyEval(Box::new(build(
+            values,
+        ))))],
+        environment: HashMap::new(),
+        name: None,
+    }
+}
 
-    Some(Value::String(input))
+/// Whether a value holds a reference to a memory slot, directly or through the references it holds
+fn refers_to(value: &Value, binding: &Binding) -> bool {
+    match value {
+        Value::Reference(target) => {
+            Rc::ptr_eq(target, binding) || refers_to(&target.borrow().value, binding)
+        }
+        Value::List(items) | Value::Tuple(items) => {
+            items.iter().any(|item| refers_to(item, binding))
+        }
+        Value::Map(entries) => entries.values().any(|value| refers_to(value, binding)),
+        Value::Record { fields, .. } => fields.iter().any(|(_, value)| refers_to(value, binding)),
+        Value::Variant { values, .. } => values.iter().any(|value| refers_to(value, binding)),
+        _ => false,
+    }
 }
 
-fn add(args: Vec<Value>) -> Option<Value> {
-    let value = args.first().unwrap();
+/// The memory slot a reference stored in a memory slot points to
+fn referent(binding: &Binding) -> Option<Binding> {
+    match &binding.borrow().value {
+        Value::Reference(target) => Some(target.clone()),
+        _ => None,
+    }
+}
 
-    match value {
-        Value::Int32(..) => {
-            let mut sum = 0i32;
+/// Assigns a value to a memory slot, or to the element of its value the accesses lead to. A slot
+/// can't hold a reference to itself, which would make it point to itself forever
+fn assign(binding: &Binding, accesses: &[Access], value: Value) -> Result<(), RuntimeError> {
+    if refers_to(&value, binding) {
+        return Err(RuntimeError::new(
+            "A variable cannot hold a reference to itself",
+        ));
+    }
 
-            for num in args {
-                match num {
-                    Value::Int32(value) => sum += value,
-                    _ => panic!("Can only add an Int with an Int"),
-                }
-            }
-            return Some(Value::Int32(sum));
+    let mut entry = binding.borrow_mut();
+
+    let Some((last, accesses)) = accesses.split_last() else {
+        entry.value = value;
+        return Ok(());
+    };
+
+    // Every access but the last one has to lead to an existing element
+    let mut element = &mut entry.value;
+    for access in accesses.iter() {
+        element = match access {
+            Access::Index(index) => index_mut(element, index)?,
+            Access::Field(name) => field_mut(element, name)?,
+        };
+    }
+
+    match last {
+        Access::Index(index) => assign_index(element, index, value),
+        // Records can't gain new fields, only existing ones are assigned to
+        Access::Field(name) => {
+            *field_mut(element, name)? = value;
+            Ok(())
         }
-        Value::Float32(..) => {
-            let mut sum = 0f32;
+    }
+}
 
-            for num in args {
-                match num {
-                    Value::Float32(value) => sum += value,
-                    _ => panic!("Can only add a Float with a Float"),
-                }
+/// Turns the call ending the body of a function into a tail call, looking into the branches of a
+/// conditional or a match ending the body as well
+fn mark_tail_calls(instructions: &mut [Instruction]) {
+    let Some(last) = instructions.last_mut() else {
+        return;
+    };
+
+    match last {
+        Instruction::ExecuteFunction(id, arguments) => {
+            *last = Instruction::TailCall(std::mem::take(id), std::mem::take(arguments), false);
+        }
+        Instruction::Conditional(conditional) => {
+            mark_tail_calls(&mut conditional.main.1);
+            for (_, body) in conditional.alternates.iter_mut() {
+                mark_tail_calls(body);
+            }
+            if let Some(fallback) = conditional.fallback.as_mut() {
+                mark_tail_calls(fallback);
             }
-            return Some(Value::Float32(sum));
         }
-        _ => panic!(),
-    };
+        Instruction::Match(instruction) => {
+            for (_, _, body) in instruction.arms.iter_mut() {
+                mark_tail_calls(body);
+            }
+        }
+        _ => {}
+    }
 }
 
-fn equals(args: Vec<Value>) -> Option<Value> {
-    if args.len() != 2 {
-        println!("Runtime error: Only expected 2 arguments for equals function");
-        panic!()
-    }
+fn say_time(name: &str, instant: Instant) {
+    let fmt_str = format!(
+        "✅ Task finished > {} done in: {}s",
+        name,
+        instant.elapsed().as_secs_f32()
+    );
+    println!("{}", fmt_str.green());
+}
 
-    let is_equals = args.first().unwrap() == args.last().unwrap();
+#[derive(Debug)]
+/// A closure being compiled, along with the variables of the enclosing functions it uses
+struct ClosureScope {
+    /// Index of the scope holding the arguments of the closure in the scope stack
+    depth: usize,
+    captures: Vec<Id>,
+}
 
-    Some(Value::Boolean(is_equals))
+#[derive(Debug)]
+/// A struct that is responsible for parsing a program and generating a list of instructions that
+/// will be fed to the engine
+pub struct Compiler<'a> {
+    scope: &'a mut Vec<HashMap<String, (Id, bool)>>,
+    pub instructions: Vec<Instruction>,
+    /// How many loops surround the statements being compiled, `break` and `continue` need one
+    loop_depth: usize,
+    /// How many scopes hold global names (builtins and top level declarations), which are never
+    /// captured as they live for the whole program
+    globals: usize,
+    closures: Vec<ClosureScope>,
+    /// The sum types of the variants declared so far, by id of the variant. Variants are found
+    /// through the scope, so that modules declaring variants with the same name don't collide
+    variants: HashMap<Id, Enumeration>,
+    /// The directory of the file being compiled, imports are relative to it
+    directory: PathBuf,
+    /// The files being compiled, each one importing the next, used to detect cyclic imports
+    importing: Vec<PathBuf>,
+    /// The exports of the modules compiled so far, so that they are only compiled once
+    modules: HashMap<PathBuf, HashMap<String, (Id, bool)>>,
+    /// The names exported by the file being compiled
+    exports: Vec<String>,
+    /// The problems found in the program that don't prevent it from running
+    pub warnings: Vec<String>,
 }
 
 impl<'a> Compiler<'a> {
@@ -253,6 +730,15 @@ impl<'a> Compiler<'a> {
         Self {
             scope,
             instructions: Vec::new(),
+            loop_depth: 0,
+            globals: 0,
+            closures: Vec::new(),
+            variants: HashMap::new(),
+            directory: PathBuf::from("."),
+            importing: Vec::new(),
+            modules: HashMap::new(),
+            exports: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -267,7 +753,10 @@ impl<'a> Compiler<'a> {
             false,
             Value::Closure {
                 instructions,
-                argument_count: 0,
+                arguments: Vec::new(),
+                rest: None,
+                environment: HashMap::new(),
+                name: None,
             },
         ));
     }
@@ -280,112 +769,295 @@ impl<'a> Compiler<'a> {
         self.add_default_func("equals", EQUALS_ID, vec![Instruction::ExtCall(equals)]);
         self.add_default_func("smaller", SMALLER_ID, vec![Instruction::ExtCall(smaller)]);
         self.add_default_func("date", DATE_ID, vec![Instruction::ExtCall(date)]);
+        self.add_default_func("big", BIG_ID, vec![Instruction::ExtCall(big)]);
         self.add_default_func("input", INPUT_ID, vec![Instruction::ExtCall(input)]);
+        self.add_default_func("len", LEN_ID, vec![Instruction::ExtCall(len)]);
+        self.add_default_func("push", PUSH_ID, vec![Instruction::ExtMutCall(push)]);
+        self.add_default_func("pop", POP_ID, vec![Instruction::ExtMutCall(pop)]);
+        self.add_default_func("keys", KEYS_ID, vec![Instruction::ExtCall(keys)]);
+        self.add_default_func("values", VALUES_ID, vec![Instruction::ExtCall(values)]);
+        self.add_default_func("has", HAS_ID, vec![Instruction::ExtCall(has)]);
+        self.add_default_func("get", GET_ID, vec![Instruction::ExtCall(get)]);
+        self.add_default_func(
+            "parse_int",
+            PARSE_INT_ID,
+            vec![Instruction::ExtCall(parse_int)],
+        );
+        self.add_default_func(
+            "parse_float",
+            PARSE_FLOAT_ID,
+            vec![Instruction::ExtCall(parse_float)],
+        );
+
+        // The values returned by the functions that can fail
+        for (name, variants) in [
+            (RESULT, [("Ok", OK_ID, 1), ("Err", ERR_ID, 1)]),
+            (OPTION, [("Some", SOME_ID, 1), ("None", NONE_ID, 0)]),
+        ] {
+            let declaration = EnumDeclaration {
+                name: name.to_string(),
+                variants: variants
+                    .into_iter()
+                    .map(|(variant, id, fields)| Variant {
+                        name: variant.to_string(),
+                        fields: vec!["value".to_string(); fields],
+                        id: id.to_string(),
+                    })
+                    .collect(),
+            };
+            let instructions = self
+                .enum_declaration(declaration)
+                .expect("the builtin sum types are valid");
+            self.instructions.extend(instructions);
+        }
 
         self.scope_in();
+        self.globals = self.scope.len();
     }
 
-    pub fn compile(&mut self, code: &str) {
-        let parse_start = Instant::now();
-        let result = program(code);
-        println!("{:#?}", result);
-        say_time("Parsing", parse_start);
-
-        if result.clone().is_err() {
-            let x = result.finish().err().unwrap();
-            let y = convert_error(code, x);
+    /// Compiles the program in a file, the files it imports are looked up relatively to it
+    pub fn compile_file(&mut self, path: &Path) -> Result<(), CompileError> {
+        let path = canonicalize(path)?;
+        let code = read_source(&path)?;
 
-            println!("Error: {y}");
-            return;
-        }
+        self.directory = path.parent().unwrap().to_path_buf();
+        self.importing.push(path);
+        self.compile(&code)
+    }
 
-        let result_program = result.unwrap();
+    pub fn compile(&mut self, code: &str) -> Result<(), CompileError> {
+        let parse_start = Instant::now();
+        let result_program = parse(code)?;
+        say_time("Parsing", parse_start);
 
         let compile_start = Instant::now();
         self.prepare_defaults();
-        let instructions = &mut self.generate_instruction(result_program.1.statements);
+        let statements = expand(result_program.statements, code)?;
+        let mut checker = Checker::new();
+        checker.declare_types(imported_types(&self.directory, &statements)?);
+        checker.check(&statements.body)?;
+        self.warn(checker.warnings);
+        self.declare_functions(&statements);
+        let instructions = &mut self.generate_instruction(statements)?;
         self.instructions.append(instructions);
         self.instructions.push(Instruction::ExecuteFunction(
-            result_program.1.main_id,
+            result_program.main_id,
             Vec::new(),
         ));
 
         say_time("Compiling", compile_start);
         println!();
         // println!("Instructions: {:?}", self.instructions);
+        Ok(())
+    }
+
+    /// Declares the functions of a file before it is compiled, so that they can call the
+    /// functions declared after them
+    fn declare_functions(&mut self, statements: &Statements) {
+        for statement in statements.body.iter() {
+            let declaration = match statement {
+                Statement::Export(declaration) => declaration.as_ref(),
+                statement => statement,
+            };
+            if let Statement::FunctionDeclaration(declaration) = declaration {
+                self.scope
+                    .last_mut()
+                    .unwrap()
+                    .insert(declaration.name.clone(), (declaration.id.clone(), false));
+            }
+        }
     }
 
-    fn generate_instruction(&mut self, statements: Statements) -> Vec<Instruction> {
+    fn warn(&mut self, warnings: Vec<String>) {
+        for warning in warnings.iter() {
+            println!("{}", format!("Warning: {warning}").yellow());
+        }
+        self.warnings.extend(warnings);
+    }
+
+    fn generate_instruction(
+        &mut self,
+        statements: Statements,
+    ) -> Result<Vec<Instruction>, CompileError> {
         let mut instructions = Vec::new();
 
         for statement in statements.body.iter() {
             match statement {
+                // Returning a call from a closure is always a tail call
+                Statement::Return(content) if !self.closures.is_empty() => match *content.clone() {
+                    Statement::FunctionCall(call) => instructions.push(Instruction::TailCall(
+                        self.resolve_variable(call.name)?.0,
+                        call.arguments
+                            .into_iter()
+                            .map(|arg| self.eval(arg))
+                            .collect::<Result<Vec<Value>, CompileError>>()?,
+                        true,
+                    )),
+                    content => instructions.push(Instruction::Return(self.eval(content)?)),
+                },
                 Statement::Return(content) => {
-                    instructions.push(Instruction::Return(self.eval(*content.clone())))
+                    instructions.push(Instruction::Return(self.eval(*content.clone())?))
                 }
                 Statement::FunctionDeclaration(declaration) => {
                     instructions.extend_from_slice(
-                        self.function_declaration(declaration.clone()).as_slice(),
+                        self.function_declaration(declaration.clone())?.as_slice(),
                     );
                 }
                 Statement::Assignement(var) => {
                     instructions
-                        .extend_from_slice(self.variable_assignement(var.clone()).as_slice());
+                        .extend_from_slice(self.variable_assignement(var.clone())?.as_slice());
                 }
                 Statement::FunctionCall(fc) => {
                     instructions.push(Instruction::ExecuteFunction(
-                        self.resolve_variable(fc.clone().name).unwrap().0,
+                        self.resolve_variable(fc.clone().name)?.0,
                         fc.arguments
                             .iter()
                             .map(|arg| self.eval(arg.clone()))
-                            .collect(),
+                            .collect::<Result<Vec<Value>, CompileError>>()?,
                     ));
                 }
                 Statement::Conditional(statement) => {
-                    instructions.push(self.conditional(statement.clone()))
+                    instructions.push(self.conditional(statement.clone())?)
+                }
+                Statement::Destructuring(destructuring) => {
+                    instructions.push(self.destructuring(destructuring.clone())?)
+                }
+                Statement::TypeDeclaration(declaration) => {
+                    instructions.push(self.type_declaration(declaration.clone())?)
+                }
+                Statement::EnumDeclaration(declaration) => {
+                    instructions.extend(self.enum_declaration(declaration.clone())?)
+                }
+                Statement::Match(statement) => {
+                    instructions.push(self.match_statement(statement.clone())?)
+                }
+                Statement::Try(value) => instructions.push(self.propagation(*value.clone())?),
+                Statement::Import(path) => instructions.extend(self.import(path.clone())?),
+                Statement::Export(declaration) => {
+                    instructions.extend(self.export(*declaration.clone())?)
+                }
+                Statement::While(statement) => {
+                    instructions.push(self.while_loop(statement.clone())?)
+                }
+                Statement::Break | Statement::Continue => {
+                    if self.loop_depth == 0 {
+                        return Err(CompileError::new(format!(
+                            "'{}' can only be used inside of a loop",
+                            if let Statement::Break = statement {
+                                "break"
+                            } else {
+                                "continue"
+                            }
+                        )));
+                    }
+
+                    instructions.push(match statement {
+                        Statement::Break => Instruction::Break,
+                        _ => Instruction::Continue,
+                    })
+                }
+                // An expression on its own is only evaluated for the calls it makes
+                Statement::String(_)
+                | Statement::Interpolation(_)
+                | Statement::Closure(_)
+                | Statement::Variable(_)
+                | Statement::Number(_)
+                | Statement::Boolean(_)
+                | Statement::Operation(_)
+                | Statement::UnaryOperation(..)
+                | Statement::List(_)
+                | Statement::Map(_)
+                | Statement::Tuple(_)
+                | Statement::Index(..)
+                | Statement::Field(..)
+                | Statement::Spread(_)
+                | Statement::Reference(_)
+                | Statement::Dereference(_) => {
+                    if let Value::LazyEval(instruction) = self.eval(statement.clone())? {
+                        instructions.push(*instruction);
+                    }
+                }
+                Statement::MacroDeclaration(declaration) => {
+                    return Err(CompileError::new(format!(
+                        "Macro '{}' has to be declared at the top level of a file",
+                        declaration.name
+                    )))
+                }
+                Statement::MacroCall(call) => {
+                    return Err(CompileError::new(format!(
+                        "Macro '{}' has to be expanded before being compiled",
+                        call.name
+                    )))
                 }
-                _ => {}
             }
         }
 
-        return instructions;
+        Ok(instructions)
     }
 
-    fn conditional(&mut self, conditional: Conditional) -> Instruction {
-        let first_condition = self.eval(conditional.main_condition.0);
+    fn conditional(&mut self, conditional: Conditional) -> Result<Instruction, CompileError> {
+        let first_condition = self.eval(conditional.main_condition.0)?;
 
         self.scope_in();
 
-        let first_body = self.generate_instruction(conditional.main_condition.1);
+        let first_body = self.generate_instruction(conditional.main_condition.1)?;
 
         self.scope_out();
 
-        let alternates: Vec<(Value, Vec<Instruction>)> = Vec::new();
+        let mut alternates: Vec<(Value, Vec<Instruction>)> = Vec::new();
 
         for alternate in conditional.alternates {
-            let condition = self.eval(alternate.0);
+            let condition = self.eval(alternate.0)?;
             self.scope_in();
-            let body = self.generate_instruction(alternate.1);
+            let body = self.generate_instruction(alternate.1)?;
             self.scope_out();
+            alternates.push((condition, body));
         }
 
         let mut fallback: Option<Vec<Instruction>> = None;
 
-        if conditional.fallback.is_some() {
-            let fb = conditional.fallback.unwrap();
+        if let Some(fb) = conditional.fallback {
             self.scope_in();
-            fallback = Some(self.generate_instruction(fb));
+            fallback = Some(self.generate_instruction(fb)?);
             self.scope_out();
         }
 
-        Instruction::Conditional(ConditionalInstruction {
+        Ok(Instruction::Conditional(ConditionalInstruction {
             main: (first_condition, first_body),
-            alternates: Some(Vec::new()),
+            alternates,
             fallback,
-        })
+        }))
+    }
+
+    fn while_loop(&mut self, while_loop: WhileLoop) -> Result<Instruction, CompileError> {
+        let condition = self.eval(*while_loop.condition)?;
+
+        self.scope_in();
+        self.loop_depth += 1;
+
+        let body = self.generate_instruction(while_loop.body);
+
+        self.loop_depth -= 1;
+        // The variables declared in the body only live for one iteration
+        let locals = self
+            .scope
+            .pop()
+            .unwrap()
+            .into_values()
+            .map(|(id, _)| id)
+            .collect();
+
+        Ok(Instruction::Loop(LoopInstruction {
+            condition,
+            body: body?,
+            locals,
+        }))
     }
 
-    fn function_declaration(&mut self, declaration: FunctionDeclaration) -> Vec<Instruction> {
+    fn function_declaration(
+        &mut self,
+        declaration: FunctionDeclaration,
+    ) -> Result<Vec<Instruction>, CompileError> {
         let mut instructions: Vec<Instruction> = Vec::new();
         /*self.scope
         .get_mut(0)
@@ -397,36 +1069,74 @@ impl<'a> Compiler<'a> {
             .unwrap()
             .insert(declaration.name.clone(), (declaration.id.clone(), false));
 
-        self.scope_in();
+        let mut closure = self.closure(declaration.closure)?;
 
-        for (index, argument) in declaration.closure.arguments.iter().enumerate() {
-            self.scope.last_mut().unwrap().insert(
-                argument.clone(),
-                ((format!("{}-{}", declaration.id.clone(), index)), false),
-            );
+        // A function declared inside of another one can't capture itself, as it doesn't exist yet
+        // when it is created
+        if let Some(index) = closure.captures.iter().position(|id| *id == declaration.id) {
+            closure.captures.remove(index);
+            closure.name = Some(declaration.id.clone());
         }
 
-        let child_instructions = self.generate_instruction(declaration.closure.body);
-        /*self.scope
-        .last_mut()
-        .unwrap()
-        .insert(declaration.name, declaration.id.clone()); */
-        println!("{:?}", declaration.closure.arguments);
-
         instructions.push(Instruction::VariableDeclaration(
             declaration.id,
             false,
-            Value::Closure {
-                instructions: child_instructions,
-                argument_count: declaration.closure.arguments.len() as u8,
-            },
+            Value::LazyEval(Box::new(Instruction::MakeClosure(closure))),
         ));
+
+        Ok(instructions)
+    }
+
+    /// Compiles the body of a closure in its own scope, where its arguments are declared
+    fn closure(&mut self, closure: Closure) -> Result<ClosureInstruction, CompileError> {
+        self.scope_in();
+        self.closures.push(ClosureScope {
+            depth: self.scope.len() - 1,
+            captures: Vec::new(),
+        });
+
+        let mut arguments = Vec::new();
+        for (index, argument) in closure.arguments.iter().enumerate() {
+            let id = format!("{}-{}", closure.id, index);
+            self.scope
+                .last_mut()
+                .unwrap()
+                .insert(argument.clone(), (id.clone(), false));
+            arguments.push(id);
+        }
+
+        let rest = closure.rest.map(|name| {
+            let id = format!("{}-rest", closure.id);
+            self.scope
+                .last_mut()
+                .unwrap()
+                .insert(name, (id.clone(), false));
+            id
+        });
+
+        // A loop surrounding the closure can't be controlled from inside of it
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let instructions = self.generate_instruction(closure.body);
+        self.loop_depth = loop_depth;
+        let captures = self.closures.pop().unwrap().captures;
         self.scope_out();
 
-        return instructions;
+        let mut instructions = instructions?;
+        mark_tail_calls(&mut instructions);
+
+        Ok(ClosureInstruction {
+            arguments,
+            rest,
+            instructions,
+            captures,
+            name: None,
+        })
     }
 
-    fn variable_assignement(&mut self, declaration: Assignement) -> Vec<Instruction> {
+    fn variable_assignement(
+        &mut self,
+        declaration: Assignement,
+    ) -> Result<Vec<Instruction>, CompileError> {
         let mut instructions = Vec::new();
 
         let is_declaration = declaration.id.is_some();
@@ -434,47 +1144,403 @@ impl<'a> Compiler<'a> {
         if is_declaration {
             let _ = &self.scope.last_mut().unwrap().insert(
                 declaration.name.clone(),
-                (declaration.id.clone().unwrap(), declaration.mutable.clone()),
+                (declaration.id.clone().unwrap(), declaration.mutable),
             );
 
             let value = declaration.value.deref().clone();
-            let value = self.eval(value);
+            let value = self.eval(value)?;
             instructions.push(Instruction::VariableDeclaration(
                 declaration.id.unwrap(),
                 declaration.mutable,
                 value,
             ));
 
-            return instructions;
+            return Ok(instructions);
         }
 
-        let (id, mutable) = self.resolve_variable(declaration.name.clone()).unwrap();
+        let (id, mutable) = self.resolve_variable(declaration.name.clone())?;
 
-        if !mutable {
-            println!(
+        // Whether the referenced variable is mutable is only known at runtime
+        if !mutable && !declaration.dereference {
+            return Err(CompileError::new(format!(
                 "Trying to assign to constant variable: {}",
                 declaration.name
-            );
-            panic!();
+            )));
         }
 
         let value = declaration.value.deref().clone();
-        let value = self.eval(value);
+        let value = self.eval(value)?;
+
+        let accesses = declaration
+            .accessors
+            .into_iter()
+            .map(|accessor| match accessor {
+                Accessor::Index(index) => Ok(Access::Index(self.eval(index)?)),
+                Accessor::Field(name) => Ok(Access::Field(name)),
+            })
+            .collect::<Result<Vec<Access>, CompileError>>()?;
+
+        if declaration.dereference {
+            instructions.push(Instruction::DerefAssignement(id, accesses, value));
+        } else if accesses.is_empty() {
+            instructions.push(Instruction::VariableAssignement(id, value));
+        } else {
+            instructions.push(Instruction::IndexAssignement(id, accesses, value));
+        }
 
-        instructions.push(Instruction::VariableAssignement(id, value));
+        Ok(instructions)
+    }
 
-        return instructions;
+    fn propagation(&mut self, value: Statement) -> Result<Instruction, CompileError> {
+        if self.closures.is_empty() {
+            return Err(CompileError::new(
+                "'?' can only be used inside of a function",
+            ));
+        }
+
+        Ok(Instruction::Propagate(self.eval(value)?))
     }
 
-    fn resolve_variable(&self, name: String) -> Option<(Id, bool)> {
-        for scope in self.scope.clone().iter().rev() {
-            let entry = scope.get(&name);
+    fn destructuring(&mut self, destructuring: Destructuring) -> Result<Instruction, CompileError> {
+        let value = self.eval(*destructuring.value)?;
+
+        for (name, id) in destructuring.names.iter().zip(destructuring.ids.iter()) {
+            self.scope
+                .last_mut()
+                .unwrap()
+                .insert(name.clone(), (id.clone(), destructuring.mutable));
+        }
+
+        Ok(Instruction::Destructure(
+            destructuring.ids,
+            destructuring.mutable,
+            value,
+        ))
+    }
 
-            if entry.is_some() {
-                return Some(entry.unwrap().clone());
+    /// Compiles an imported file the first time it is imported, then makes its exports visible
+    /// under its name: `math.square` for the `square` function of `math.msq`
+    fn import(&mut self, segments: Vec<String>) -> Result<Vec<Instruction>, CompileError> {
+        if self.scope.len() != self.globals {
+            return Err(CompileError::new(
+                "Modules can only be imported at the top level of a file",
+            ));
+        }
+
+        let namespace = segments.last().unwrap().clone();
+        let path = module_path(&self.directory, &segments)?;
+
+        if let Some(start) = self.importing.iter().position(|file| *file == path) {
+            let cycle: Vec<String> = self.importing[start..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+
+            return Err(CompileError::new(format!(
+                "Cyclic import: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let mut instructions = Vec::new();
+
+        let exports = match self.modules.get(&path) {
+            Some(exports) => exports.clone(),
+            None => {
+                let code = read_source(&path)?;
+                let (statements, warnings) = parse(&code)
+                    .and_then(|module| expand(module.statements, &code))
+                    .and_then(|statements| {
+                        let mut checker = Checker::new();
+                        checker.declare_types(imported_types(path.parent().unwrap(), &statements)?);
+                        checker.check(&statements.body)?;
+                        Ok((statements, checker.warnings))
+                    })
+                    .map_err(|error| {
+                        CompileError::new(format!("In {}:\n{}", path.display(), error.message))
+                    })?;
+                self.warn(
+                    warnings
+                        .into_iter()
+                        .map(|warning| format!("In {}: {warning}", path.display()))
+                        .collect(),
+                );
+
+                // The module is compiled in its own top level scope, it can't see the names of
+                // the importing file
+                let directory =
+                    std::mem::replace(&mut self.directory, path.parent().unwrap().to_path_buf());
+                let exports = std::mem::take(&mut self.exports);
+                let importer = std::mem::take(self.scope.last_mut().unwrap());
+                self.importing.push(path.clone());
+
+                self.declare_functions(&statements);
+                let result = self.generate_instruction(statements);
+
+                self.importing.pop();
+                let scope = std::mem::replace(self.scope.last_mut().unwrap(), importer);
+                let exported = std::mem::replace(&mut self.exports, exports);
+                self.directory = directory;
+
+                instructions = result?;
+
+                let exports: HashMap<String, (Id, bool)> = scope
+                    .into_iter()
+                    .filter(|(name, _)| exported.contains(name))
+                    .collect();
+                self.modules.insert(path, exports.clone());
+                exports
             }
+        };
+
+        let scope = self.scope.last_mut().unwrap();
+        for (name, entry) in exports {
+            scope.insert(format!("{namespace}.{name}"), entry);
         }
-        None
+
+        Ok(instructions)
+    }
+
+    fn export(&mut self, declaration: Statement) -> Result<Vec<Instruction>, CompileError> {
+        if self.scope.len() != self.globals {
+            return Err(CompileError::new(
+                "Only top level declarations can be exported",
+            ));
+        }
+
+        let names = match &declaration {
+            Statement::FunctionDeclaration(declaration) => vec![declaration.name.clone()],
+            Statement::TypeDeclaration(declaration) => vec![declaration.name.clone()],
+            Statement::EnumDeclaration(declaration) => declaration
+                .variants
+                .iter()
+                .map(|variant| variant.name.clone())
+                .collect(),
+            Statement::Assignement(assignement) if assignement.declaration => {
+                vec![assignement.name.clone()]
+            }
+            Statement::Destructuring(destructuring) => destructuring.names.clone(),
+            _ => return Err(CompileError::new("Only declarations can be exported")),
+        };
+
+        self.exports.extend(names);
+        self.generate_instruction(Statements {
+            body: vec![declaration],
+        })
+    }
+
+    /// Declares the constructor of a record type, a closure taking the value of each field
+    fn type_declaration(
+        &mut self,
+        declaration: TypeDeclaration,
+    ) -> Result<Instruction, CompileError> {
+        for (index, field) in declaration.fields.iter().enumerate() {
+            if declaration.fields[..index].contains(field) {
+                return Err(CompileError::new(format!(
+                    "Field '{field}' is declared twice in type {}",
+                    declaration.name
+                )));
+            }
+        }
+
+        self.scope
+            .last_mut()
+            .unwrap()
+            .insert(declaration.name.clone(), (declaration.id.clone(), false));
+
+        let constructor = constructor(&declaration.id, declaration.fields.len(), |values| {
+            let fields = declaration.fields.into_iter().zip(values).collect();
+            Instruction::BuildRecord(declaration.name, fields)
+        });
+
+        Ok(Instruction::VariableDeclaration(
+            declaration.id,
+            false,
+            constructor,
+        ))
+    }
+
+    /// Declares the constructors of the variants of a sum type, variants holding no value are
+    /// declared as values directly
+    fn enum_declaration(
+        &mut self,
+        declaration: EnumDeclaration,
+    ) -> Result<Vec<Instruction>, CompileError> {
+        let mut variants: Vec<(String, usize)> = Vec::new();
+        let mut ids = Vec::new();
+        let mut instructions = Vec::new();
+
+        for variant in declaration.variants {
+            if let Some((_, (enumeration, _))) = self.find_variant(&variant.name) {
+                return Err(CompileError::new(format!(
+                    "Variant {} is already declared in type {enumeration}",
+                    variant.name
+                )));
+            }
+            if variants.iter().any(|(name, _)| *name == variant.name) {
+                return Err(CompileError::new(format!(
+                    "Variant {} is declared twice in type {}",
+                    variant.name, declaration.name
+                )));
+            }
+
+            self.scope
+                .last_mut()
+                .unwrap()
+                .insert(variant.name.clone(), (variant.id.clone(), false));
+            variants.push((variant.name.clone(), variant.fields.len()));
+            ids.push(variant.id.clone());
+
+            let enumeration = declaration.name.clone();
+            let value = if variant.fields.is_empty() {
+                Value::Variant {
+                    enumeration,
+                    variant: variant.name,
+                    values: Vec::new(),
+                }
+            } else {
+                constructor(&variant.id, variant.fields.len(), |values| {
+                    Instruction::BuildVariant(enumeration, variant.name, values)
+                })
+            };
+
+            instructions.push(Instruction::VariableDeclaration(variant.id, false, value));
+        }
+
+        for id in ids {
+            self.variants
+                .insert(id, (declaration.name.clone(), variants.clone()));
+        }
+        Ok(instructions)
+    }
+
+    /// The variant a name refers to in the scope, `shapes.Circle` for a variant exported by a
+    /// module. Returns the name of the variant, along with its sum type and the variants of it
+    fn find_variant(&self, name: &str) -> Option<(String, &Enumeration)> {
+        let (id, _) = self.scope.iter().rev().find_map(|scope| scope.get(name))?;
+        let variant = name.rsplit('.').next().unwrap_or(name).to_string();
+        self.variants
+            .get(id)
+            .map(|enumeration| (variant, enumeration))
+    }
+
+    /// Compiles a match, making sure that every variant of the matched type is handled once
+    fn match_statement(&mut self, statement: Match) -> Result<Instruction, CompileError> {
+        let value = self.eval(*statement.value)?;
+
+        let mut enumeration: Option<Enumeration> = None;
+        let mut handled: Vec<String> = Vec::new();
+        let mut has_wildcard = false;
+        let mut arms = Vec::new();
+
+        for arm in statement.arms {
+            if has_wildcard {
+                return Err(CompileError::new(
+                    "The '_' arm has to be the last one of a match",
+                ));
+            }
+
+            let variant = match &arm.variant {
+                Some(name) => {
+                    let (variant, (owner, variants)) = self
+                        .find_variant(name)
+                        .ok_or_else(|| CompileError::new(format!("Unknown variant: {name}")))?;
+                    let (expected, _) =
+                        enumeration.get_or_insert_with(|| (owner.clone(), variants.clone()));
+
+                    if owner != expected {
+                        return Err(CompileError::new(format!(
+                            "Variant {variant} belongs to type {owner}, but the match is on a {expected}"
+                        )));
+                    }
+
+                    let fields = variants
+                        .iter()
+                        .find(|(name, _)| *name == variant)
+                        .map(|(_, fields)| *fields)
+                        .unwrap();
+
+                    if fields != arm.names.len() {
+                        return Err(CompileError::new(format!(
+                            "Variant {variant} holds {fields} values, but its pattern binds {}",
+                            arm.names.len()
+                        )));
+                    }
+                    if handled.contains(&variant) {
+                        return Err(CompileError::new(format!(
+                            "Variant {variant} is matched twice"
+                        )));
+                    }
+
+                    handled.push(variant.clone());
+                    Some(variant)
+                }
+                None => {
+                    has_wildcard = true;
+                    None
+                }
+            };
+
+            self.scope_in();
+            for (name, id) in arm.names.iter().zip(arm.ids.iter()) {
+                self.scope
+                    .last_mut()
+                    .unwrap()
+                    .insert(name.clone(), (id.clone(), false));
+            }
+            let body = self.generate_instruction(arm.body);
+            self.scope_out();
+
+            arms.push((variant, arm.ids, body?));
+        }
+
+        if !has_wildcard {
+            let Some((enumeration, variants)) = &enumeration else {
+                return Err(CompileError::new("A match needs at least one arm"));
+            };
+
+            let missing: Vec<&str> = variants
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .filter(|name| !handled.iter().any(|handled| handled == name))
+                .collect();
+
+            if !missing.is_empty() {
+                return Err(CompileError::new(format!(
+                    "The match on {enumeration} does not handle {}",
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        Ok(Instruction::Match(MatchInstruction {
+            value,
+            enumeration: enumeration.map(|(name, _)| name),
+            arms,
+        }))
+    }
+
+    fn resolve_variable(&mut self, name: String) -> Result<(Id, bool), CompileError> {
+        for (depth, scope) in self.scope.iter().enumerate().rev() {
+            if let Some(entry) = scope.get(&name) {
+                if depth >= self.globals {
+                    // Every closure between the declaration and its use has to capture it
+                    for closure in self.closures.iter_mut().rev() {
+                        if closure.depth <= depth {
+                            break;
+                        }
+                        if !closure.captures.contains(&entry.0) {
+                            closure.captures.push(entry.0.clone());
+                        }
+                    }
+                }
+
+                return Ok(entry.clone());
+            }
+        }
+
+        Err(CompileError::new(format!("Unknown variable: {name}")))
     }
 
     /// Used when entering a nested scope, creates said scope in the scope stack.
@@ -488,173 +1554,797 @@ impl<'a> Compiler<'a> {
         self.scope.pop();
     }
 
-    fn eval(&self, statement: Statement) -> Value {
-        match statement {
+    fn eval(&mut self, statement: Statement) -> Result<Value, CompileError> {
+        Ok(match statement {
             Statement::String(value) => Value::String(value),
             Statement::Number(number) => match number {
-                Number::Int(value) => Value::Int32(value),
-                Number::Float(value) => Value::Float32(value),
+                Number::Int(value) => Value::Int64(value),
+                Number::Float(value) => Value::Float64(value),
             },
-            Statement::Closure(_) => todo!(),
-            Statement::Variable(name) => {
-                println!("Resolving: {name}");
-                println!("{:#?}", self.scope);
-                Value::CopyVar(self.resolve_variable(name).unwrap().0, Box::new(None))
+            Statement::Closure(closure) => {
+                Value::LazyEval(Box::new(Instruction::MakeClosure(self.closure(closure)?)))
             }
+            Statement::Variable(name) => Value::CopyVar(self.resolve_variable(name)?.0),
             Statement::Boolean(value) => Value::Boolean(value),
             Statement::FunctionCall(call) => {
                 Value::LazyEval(Box::new(Instruction::ExecuteFunction(
-                    self.resolve_variable(call.name).unwrap().0,
+                    self.resolve_variable(call.name)?.0,
                     call.arguments
                         .iter()
                         .map(|arg| self.eval(arg.clone()))
-                        .collect(),
+                        .collect::<Result<Vec<Value>, CompileError>>()?,
                 )))
             }
-            _ => Value::String(String::new()),
-        }
+            Statement::Operation(operation) => {
+                let operation = *operation;
+                Value::LazyEval(Box::new(Instruction::Operation(
+                    operation.operator,
+                    self.eval(operation.left)?,
+                    self.eval(operation.right)?,
+                )))
+            }
+            Statement::UnaryOperation(operator, value) => Value::LazyEval(Box::new(
+                Instruction::UnaryOperation(operator, self.eval(*value)?),
+            )),
+            Statement::List(items) => Value::LazyEval(Box::new(Instruction::BuildList(
+                items
+                    .into_iter()
+                    .map(|item| self.eval(item))
+                    .collect::<Result<Vec<Value>, CompileError>>()?,
+            ))),
+            Statement::Map(entries) => Value::LazyEval(Box::new(Instruction::BuildMap(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| Ok((self.eval(key)?, self.eval(value)?)))
+                    .collect::<Result<Vec<(Value, Value)>, CompileError>>()?,
+            ))),
+            Statement::Tuple(items) => Value::LazyEval(Box::new(Instruction::BuildTuple(
+                items
+                    .into_iter()
+                    .map(|item| self.eval(item))
+                    .collect::<Result<Vec<Value>, CompileError>>()?,
+            ))),
+            Statement::Index(list, index) => Value::LazyEval(Box::new(Instruction::Index(
+                self.eval(*list)?,
+                self.eval(*index)?,
+            ))),
+            Statement::Field(record, name) => {
+                // `math.pi` reads the `pi` variable exported by the `math` module
+                if let Statement::Variable(namespace) = record.as_ref() {
+                    if let Ok((id, _)) = self.resolve_variable(format!("{namespace}.{name}")) {
+                        return Ok(Value::CopyVar(id));
+                    }
+                }
+
+                Value::LazyEval(Box::new(Instruction::Field(self.eval(*record)?, name)))
+            }
+            Statement::Interpolation(parts) => Value::LazyEval(Box::new(Instruction::Concat(
+                parts
+                    .into_iter()
+                    .map(|part| self.eval(part))
+                    .collect::<Result<Vec<Value>, CompileError>>()?,
+            ))),
+            Statement::Spread(list) => {
+                Value::LazyEval(Box::new(Instruction::Spread(self.eval(*list)?)))
+            }
+            Statement::Try(value) => Value::LazyEval(Box::new(self.propagation(*value)?)),
+            Statement::Reference(name) => Value::LazyEval(Box::new(Instruction::Reference(
+                self.resolve_variable(name)?.0,
+            ))),
+            Statement::Dereference(value) => {
+                Value::LazyEval(Box::new(Instruction::Dereference(self.eval(*value)?)))
+            }
+            Statement::Assignement(_) | Statement::Destructuring(_) => {
+                return Err(CompileError::new("An assignment cannot be used as a value"))
+            }
+            Statement::FunctionDeclaration(_)
+            | Statement::TypeDeclaration(_)
+            | Statement::EnumDeclaration(_)
+            | Statement::MacroDeclaration(_)
+            | Statement::Import(_)
+            | Statement::Export(_) => {
+                return Err(CompileError::new("A declaration cannot be used as a value"))
+            }
+            Statement::Conditional(_) | Statement::Match(_) | Statement::While(_) => {
+                return Err(CompileError::new(
+                    "Conditions, matches and loops cannot be used as values",
+                ))
+            }
+            Statement::Return(_) | Statement::Break | Statement::Continue => {
+                return Err(CompileError::new(
+                    "'return', 'break' and 'continue' cannot be used as values",
+                ))
+            }
+            Statement::MacroCall(call) => {
+                return Err(CompileError::new(format!(
+                    "Macro '{}' has to be expanded before being compiled",
+                    call.name
+                )))
+            }
+        })
     }
 }
 
 pub struct Engine {
-    context: HashMap<(Id, u8), Value>,
-    instructions: Vec<Instruction>,
-    memory: DashMap<String, Entry>,
+    /// The global variables, declared outside of any function
+    memory: DashMap<String, Binding>,
+    /// The call stack, variables are looked up in the frame of the function being executed before
+    /// the global memory
+    frames: Vec<Frame>,
 }
 
 impl Engine {
     pub fn new() -> Self {
         Engine {
-            context: HashMap::new(),
-            instructions: Vec::new(),
             memory: DashMap::new(),
+            frames: Vec::new(),
         }
     }
 
-    pub fn execute_program(&mut self, instructions: Vec<Instruction>) {
+    /// Executes the instructions of a program and returns the value returned by its main function
+    pub fn execute_program(
+        &mut self,
+        instructions: Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
         let execute_start = Instant::now();
-        self.execute(instructions);
+
+        // The compiler ends every program with the call to its main function
+        let result = match instructions.split_last() {
+            Some((Instruction::ExecuteFunction(id, arguments), declarations)) => self
+                .execute(declarations)
+                .and_then(|_| self.function_call(id.clone(), arguments.clone())),
+            _ => self.execute(&instructions).map(|_| None),
+        };
+
         say_time("Executing", execute_start);
         println!();
+        result
     }
 
-    fn execute(&mut self, instructions: Vec<Instruction>) -> Option<Value> {
+    fn execute(&mut self, instructions: &[Instruction]) -> Result<Flow, RuntimeError> {
         for instruction in instructions.iter() {
             match instruction.clone() {
                 Instruction::Return(value) => {
-                    return Some(self.eval(value));
+                    return Ok(Flow::Return(self.eval(value)?));
                 }
-                Instruction::Test(_) => todo!(),
                 Instruction::VariableDeclaration(id, mutable, value) => {
-                    self.variable_declaration(id, mutable, value);
+                    self.variable_declaration(id, mutable, value)?;
                 }
-                Instruction::VariableAssignement(id, value) => self.variable_assignement(id, value),
-                Instruction::PopVariable(_) => todo!(),
-                Instruction::ExecuteFunction(id, args) => match self.function_call(id, args) {
-                    Some(value) => return Some(value),
-                    None => {}
+                Instruction::Destructure(ids, mutable, value) => {
+                    self.destructure(ids, mutable, value)?;
+                }
+                Instruction::VariableAssignement(id, value) => {
+                    self.variable_assignement(id, value)?
+                }
+                Instruction::IndexAssignement(id, accesses, value) => {
+                    self.index_assignement(id, accesses, value)?
+                }
+                Instruction::DerefAssignement(id, accesses, value) => {
+                    self.deref_assignement(id, accesses, value)?
+                }
+                Instruction::ExecuteFunction(id, args) => {
+                    self.function_call(id, args)?;
+                }
+                Instruction::TailCall(id, args, returned) => {
+                    match self.tail_call(id, args, returned)? {
+                        Flow::Next => {}
+                        flow => return Ok(flow),
+                    }
+                }
+                Instruction::Conditional(instruction) => match self.conditional(instruction)? {
+                    Flow::Next => {}
+                    flow => return Ok(flow),
+                },
+                Instruction::Match(instruction) => match self.match_statement(instruction)? {
+                    Flow::Next => {}
+                    flow => return Ok(flow),
                 },
-                Instruction::Conditional(instruction) => match self.conditional(instruction) {
-                    Some(value) => return Some(value),
-                    None => {}
+                Instruction::Loop(instruction) => match self.while_loop(instruction)? {
+                    Flow::Next => {}
+                    flow => return Ok(flow),
                 },
-                Instruction::ExtCall(_) => todo!(),
+                Instruction::Break => return Ok(Flow::Break),
+                Instruction::Continue => return Ok(Flow::Continue),
+                instruction @ (Instruction::Operation(..)
+                | Instruction::UnaryOperation(..)
+                | Instruction::BuildList(_)
+                | Instruction::BuildMap(_)
+                | Instruction::BuildTuple(_)
+                | Instruction::Index(..)
+                | Instruction::BuildRecord(..)
+                | Instruction::BuildVariant(..)
+                | Instruction::Concat(_)
+                | Instruction::Spread(_)
+                | Instruction::Field(..)
+                | Instruction::MakeClosure(_)
+                | Instruction::Propagate(_)
+                | Instruction::Reference(_)
+                | Instruction::Dereference(_)) => {
+                    self.evaluate_instruction(instruction)?;
+                }
+                Instruction::ExtCall(_) | Instruction::ExtMutCall(_) => unreachable!(
+                    "native functions are called by prepare_call, their body is never executed"
+                ),
             }
         }
 
-        None
+        Ok(Flow::Next)
     }
 
-    fn variable_declaration(&mut self, id: String, mutable: bool, value: Value) {
-        let value = self.eval(value);
+    fn variable_declaration(
+        &mut self,
+        id: String,
+        mutable: bool,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let value = self.eval(value)?;
 
-        self.memory.insert(id, Entry { mutable, value });
+        self.declare(id, Entry { mutable, value });
+        Ok(())
     }
 
-    fn conditional(&mut self, instruction: ConditionalInstruction) -> Option<Value> {
-        let val = self.eval(instruction.main.0);
+    /// Stores a new variable in the frame of the function being executed, or in the global memory
+    /// outside of functions
+    fn declare(&mut self, id: Id, entry: Entry) {
+        let binding = Rc::new(RefCell::new(entry));
 
-        match val {
-            Value::Boolean(is_true) => {
-                if is_true {
-                    let value = self.execute(instruction.main.1);
-                    if value.is_some() {
-                        return value;
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.insert(id, binding);
+            }
+            None => {
+                self.memory.insert(id, binding);
+            }
+        }
+    }
+
+    fn destructure(
+        &mut self,
+        ids: Vec<Id>,
+        mutable: bool,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let items = match self.eval(value)? {
+            Value::Tuple(items) if items.len() == ids.len() => items,
+            Value::Tuple(items) => {
+                return Err(RuntimeError::new(format!(
+                    "Cannot unpack a Tuple of {} elements into {} variables",
+                    items.len(),
+                    ids.len()
+                )))
+            }
+            value => {
+                return Err(RuntimeError::new(format!(
+                    "Expected a Tuple to unpack, found {}",
+                    value.type_name()
+                )))
+            }
+        };
+
+        for (id, value) in ids.into_iter().zip(items) {
+            self.declare(id, Entry { mutable, value });
+        }
+
+        Ok(())
+    }
+
+    fn condition(&mut self, condition: Value, statement: &str) -> Result<bool, RuntimeError> {
+        match self.eval(condition)? {
+            Value::Boolean(value) => Ok(value),
+            value => Err(RuntimeError::new(format!(
+                "Expected a Boolean as the condition of {statement}, found {}",
+                value.type_name()
+            ))),
+        }
+    }
+
+    fn conditional(&mut self, instruction: ConditionalInstruction) -> Result<Flow, RuntimeError> {
+        let branches = std::iter::once(instruction.main).chain(instruction.alternates);
+
+        for (condition, body) in branches {
+            if self.condition(condition, "an if statement")? {
+                return self.execute(&body);
+            }
+        }
+
+        match instruction.fallback {
+            Some(fallback) => self.execute(&fallback),
+            None => Ok(Flow::Next),
+        }
+    }
+
+    fn match_statement(&mut self, instruction: MatchInstruction) -> Result<Flow, RuntimeError> {
+        let (enumeration, variant, values) = match self.eval(instruction.value)? {
+            Value::Variant {
+                enumeration,
+                variant,
+                values,
+            } => (enumeration, variant, values),
+            value => {
+                return Err(RuntimeError::new(format!(
+                    "Cannot match on a value of type {}",
+                    value.type_name()
+                )))
+            }
+        };
+
+        if let Some(expected) = instruction.enumeration {
+            if expected != enumeration {
+                return Err(RuntimeError::new(format!(
+                    "Expected a {expected} to match, found a {enumeration}"
+                )));
+            }
+        }
+
+        for (pattern, ids, body) in instruction.arms {
+            match pattern {
+                Some(name) if name != variant => continue,
+                Some(_) => {
+                    for (id, value) in ids.into_iter().zip(values) {
+                        self.declare(
+                            id,
+                            Entry {
+                                mutable: false,
+                                value,
+                            },
+                        );
                     }
                 }
+                None => {}
+            }
+
+            return self.execute(&body);
+        }
+
+        Err(RuntimeError::new(format!(
+            "No arm of the match handles {variant}"
+        )))
+    }
+
+    fn while_loop(&mut self, instruction: LoopInstruction) -> Result<Flow, RuntimeError> {
+        while self.condition(instruction.condition.clone(), "a while loop")? {
+            let flow = self.execute(&instruction.body);
+
+            for id in instruction.locals.iter() {
+                self.pop_value(id);
+            }
+
+            match flow? {
+                Flow::Break => break,
+                Flow::Next | Flow::Continue => {}
+                flow @ (Flow::Return(_) | Flow::TailCall(..)) => return Ok(flow),
             }
-            _ => {}
         }
 
-        None
+        Ok(Flow::Next)
     }
 
-    fn variable_assignement(&mut self, id: String, value: Value) {
-        self.memory.get_mut(&id).unwrap().value = self.eval(value);
+    fn variable_assignement(&mut self, id: String, value: Value) -> Result<(), RuntimeError> {
+        let value = self.eval(value)?;
+
+        assign(&self.binding(&id)?, &[], value)
+    }
+
+    fn index_assignement(
+        &mut self,
+        id: String,
+        accesses: Vec<Access>,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let accesses = self.eval_accesses(accesses)?;
+        let value = self.eval(value)?;
+
+        assign(&self.binding(&id)?, &accesses, value)
+    }
+
+    fn deref_assignement(
+        &mut self,
+        id: String,
+        accesses: Vec<Access>,
+        value: Value,
+    ) -> Result<(), RuntimeError> {
+        let accesses = self.eval_accesses(accesses)?;
+        let value = self.eval(value)?;
+
+        let target = match &self.binding(&id)?.borrow().value {
+            Value::Reference(target) => target.clone(),
+            value => {
+                return Err(RuntimeError::new(format!(
+                    "Expected a Reference after 'deref', found {}",
+                    value.type_name()
+                )))
+            }
+        };
+
+        if !target.borrow().mutable {
+            return Err(RuntimeError::new(
+                "Cannot assign through a reference to a constant variable",
+            ));
+        }
+
+        assign(&target, &accesses, value)
     }
 
-    pub fn function_call(&mut self, id: Id, arguments: Vec<Value>) -> Option<Value> {
-        let mem = &self.memory.clone();
-        let func = mem.get(&id).unwrap().clone();
-        let func = func.value;
+    fn eval_accesses(&mut self, accesses: Vec<Access>) -> Result<Vec<Access>, RuntimeError> {
+        accesses
+            .into_iter()
+            .map(|access| match access {
+                Access::Index(index) => Ok(Access::Index(self.eval(index)?)),
+                access => Ok(access),
+            })
+            .collect()
+    }
+    pub fn function_call(
+        &mut self,
+        id: Id,
+        arguments: Vec<Value>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let func = self.binding(&id)?.borrow().value.clone();
+
+        match self.prepare_call(func, arguments)? {
+            Call::Native(result) => Ok(result),
+            Call::Closure(frame, instructions) => self.call_closure(frame, instructions),
+        }
+    }
 
-        let (argument_count, instructions) = match func {
+    /// Evaluates the arguments of a call, native functions are called right away while closures
+    /// get the frame they will be executed in
+    fn prepare_call(&mut self, func: Value, arguments: Vec<Value>) -> Result<Call, RuntimeError> {
+        let (parameters, rest, instructions, environment, name) = match func.clone() {
             Value::Closure {
-                argument_count,
+                arguments,
+                rest,
                 instructions,
-            } => (argument_count, instructions),
-            _ => {
-                println!("Runtime Error: Trying to call a function at memory slot '{}', but no function was found there!", {id});
-                panic!();
+                environment,
+                name,
+            } => (arguments, rest, instructions, environment, name),
+            value => {
+                return Err(RuntimeError::new(format!(
+                    "Cannot call a value of type {}, only closures can be called",
+                    value.type_name()
+                )));
             }
         };
 
-        let arguments = arguments.iter().map(|arg| self.eval(arg.clone())).collect();
+        if let [Instruction::ExtMutCall(callee)] = instructions.as_slice() {
+            return Ok(Call::Native(self.mutable_call(*callee, arguments)?));
+        }
+
+        let mut arguments = self.eval_items(arguments)?;
+
+        if let [Instruction::ExtCall(callee)] = instructions.as_slice() {
+            return Ok(Call::Native(callee(arguments)?));
+        }
+
+        match rest {
+            None if parameters.len() != arguments.len() => {
+                return Err(RuntimeError::new(format!(
+                    "Expected {} arguments but got {}",
+                    parameters.len(),
+                    arguments.len()
+                )));
+            }
+            Some(_) if parameters.len() > arguments.len() => {
+                return Err(RuntimeError::new(format!(
+                    "Expected at least {} arguments but got {}",
+                    parameters.len(),
+                    arguments.len()
+                )));
+            }
+            _ => {}
+        }
+
+        // The frame starts with the captured variables, the arguments are added to it
+        let mut frame = environment;
+        if let Some(rest) = rest {
+            let entry = Entry {
+                mutable: false,
+                value: Value::List(arguments.split_off(parameters.len())),
+            };
+            frame.insert(rest, Rc::new(RefCell::new(entry)));
+        }
+        for (parameter, value) in parameters.into_iter().zip(arguments) {
+            let entry = Entry {
+                mutable: false,
+                value,
+            };
+            frame.insert(parameter, Rc::new(RefCell::new(entry)));
+        }
+
+        if let Some(name) = name {
+            let entry = Entry {
+                mutable: false,
+                value: func,
+            };
+            frame.insert(name, Rc::new(RefCell::new(entry)));
+        }
 
-        if instructions.len() == 1 {
-            match instructions.first().unwrap() {
-                Instruction::ExtCall(callee) => {
-                    return callee(arguments);
+        Ok(Call::Closure(frame, instructions))
+    }
+
+    /// Executes a closure in its frame. Tail calls replace the frame of the closure instead of
+    /// stacking a new one on top of it, so they run in a loop rather than recursively
+    fn call_closure(
+        &mut self,
+        mut frame: Frame,
+        mut instructions: Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        loop {
+            self.frames.push(frame);
+            let flow = self.execute(&instructions);
+            self.frames.pop();
+
+            let flow = match flow {
+                Err(RuntimeError {
+                    propagated: Some(value),
+                    ..
+                }) => Flow::Return(*value),
+                flow => flow?,
+            };
+
+            match flow {
+                Flow::Return(value) => return Ok(Some(value)),
+                Flow::TailCall(next_frame, next_instructions) => {
+                    frame = next_frame;
+                    instructions = next_instructions;
                 }
-                _ => {}
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    fn tail_call(
+        &mut self,
+        id: Id,
+        arguments: Vec<Value>,
+        returned: bool,
+    ) -> Result<Flow, RuntimeError> {
+        let func = self.binding(&id)?.borrow().value.clone();
+
+        match self.prepare_call(func, arguments)? {
+            Call::Closure(frame, instructions) => Ok(Flow::TailCall(frame, instructions)),
+            Call::Native(None) if !returned => Ok(Flow::Next),
+            Call::Native(Some(value)) => Ok(Flow::Return(value)),
+            Call::Native(None) => Err(RuntimeError::new(
+                "Expected a value but the function did not return anything",
+            )),
+        }
+    }
+
+    /// Calls a native function that modifies the variable passed as its first argument
+    fn mutable_call(
+        &mut self,
+        callee: fn(&mut Value, Vec<Value>) -> Result<Option<Value>, RuntimeError>,
+        mut arguments: Vec<Value>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let id = match arguments.first() {
+            Some(Value::CopyVar(id)) => id.clone(),
+            _ => {
+                return Err(RuntimeError::new(
+                    "Expected a mutable variable as the first argument",
+                ))
             }
+        };
+
+        let arguments = self.eval_items(arguments.split_off(1))?;
+
+        // A variable holding a reference modifies the variable it points to
+        let mut binding = self.binding(&id)?;
+        while let Some(target) = referent(&binding) {
+            binding = target;
         }
 
-        for (index, argument) in arguments.iter().enumerate() {
-            self.variable_declaration(format!("{}-{}", id, index), false, argument.clone());
+        if arguments
+            .iter()
+            .any(|argument| refers_to(argument, &binding))
+        {
+            return Err(RuntimeError::new(
+                "A variable cannot hold a reference to itself",
+            ));
+        }
+
+        let mut entry = binding.borrow_mut();
+
+        if !entry.mutable {
+            return Err(RuntimeError::new(
+                "Expected a mutable variable as the first argument, but it is constant",
+            ));
+        }
+
+        callee(&mut entry.value, arguments)
+    }
+
+    /// Finds the memory slot of a variable in the current frame, then in the global memory
+    fn binding(&self, id: &Id) -> Result<Binding, RuntimeError> {
+        if let Some(binding) = self.frames.last().and_then(|frame| frame.get(id)) {
+            return Ok(binding.clone());
         }
 
-        let return_value = self.execute(instructions);
+        match self.memory.get(id) {
+            Some(binding) => Ok(binding.clone()),
+            None => Err(RuntimeError::new(format!(
+                "Trying to access memory slot '{id}', but it was never declared"
+            ))),
+        }
+    }
 
-        for (index, argument) in arguments.iter().enumerate() {
-            self.pop_value(format!("{}-{}", id, index));
+    fn pop_value(&mut self, id: &Id) {
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.remove(id);
+            }
+            None => {
+                self.memory.remove(id);
+            }
         }
+    }
+    /// Evaluates an instruction that produces a value (operations, function calls)
+    fn evaluate_instruction(&mut self, instruction: Instruction) -> Result<Value, RuntimeError> {
+        match instruction {
+            Instruction::Operation(operator, left, right) => {
+                let left = self.eval(left)?;
+
+                // `and` and `or` short-circuit: the right side isn't evaluated when the left side
+                // already decides the result
+                match (operator, &left) {
+                    (Operator::And, Value::Boolean(false))
+                    | (Operator::Or, Value::Boolean(true)) => return Ok(left),
+                    _ => {}
+                }
+
+                let right = self.eval(right)?;
+                binary_operation(operator, left, right)
+            }
+            Instruction::UnaryOperation(operator, value) => {
+                let value = self.eval(value)?;
+                unary_operation(operator, value)
+            }
+            Instruction::BuildList(items) => Ok(Value::List(self.eval_items(items)?)),
+            Instruction::BuildTuple(items) => Ok(Value::Tuple(self.eval_items(items)?)),
+            Instruction::Concat(parts) => {
+                let mut content = String::new();
+                for part in parts {
+                    content.push_str(&self.eval(part)?.to_string());
+                }
+                Ok(Value::String(content))
+            }
+            Instruction::Spread(_) => Err(RuntimeError::new(
+                "A list can only be spread into the arguments of a call or the items of a list",
+            )),
+            Instruction::BuildMap(entries) => {
+                let mut map = HashMap::new();
+                for (key, value) in entries {
+                    let key = Key::from_value(self.eval(key)?)?;
+                    map.insert(key, self.eval(value)?);
+                }
+                Ok(Value::Map(map))
+            }
+            Instruction::Index(list, index) => {
+                let list = self.eval(list)?;
+                let index = self.eval(index)?;
+                Ok(index_ref(&list, &index)?.clone())
+            }
+            Instruction::BuildRecord(name, fields) => Ok(Value::Record {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(field, value)| Ok((field, self.eval(value)?)))
+                    .collect::<Result<Vec<(String, Value)>, RuntimeError>>()?,
+            }),
+            Instruction::Field(record, name) => {
+                let record = self.eval(record)?;
+                Ok(field_ref(&record, &name)?.clone())
+            }
+            Instruction::Propagate(value) => match self.eval(value)? {
+                Value::Variant {
+                    enumeration,
+                    variant,
+                    mut values,
+                } if enumeration == RESULT || enumeration == OPTION => match variant.as_str() {
+                    "Ok" | "Some" => Ok(values.remove(0)),
+                    _ => Err(RuntimeError::propagate(Value::Variant {
+                        enumeration,
+                        variant,
+                        values,
+                    })),
+                },
+                value => Err(RuntimeError::new(format!(
+                    "Expected a Result or an Option before '?', found {}",
+                    value.type_name()
+                ))),
+            },
+            Instruction::Reference(id) => Ok(Value::Reference(self.binding(&id)?)),
+            Instruction::Dereference(value) => match self.eval(value)? {
+                Value::Reference(target) => Ok(target.borrow().value.clone()),
+                value => Err(RuntimeError::new(format!(
+                    "Expected a Reference after 'deref', found {}",
+                    value.type_name()
+                ))),
+            },
+            Instruction::BuildVariant(enumeration, variant, values) => Ok(Value::Variant {
+                enumeration,
+                variant,
+                values: values
+                    .into_iter()
+                    .map(|value| self.eval(value))
+                    .collect::<Result<Vec<Value>, RuntimeError>>()?,
+            }),
+            Instruction::MakeClosure(closure) => {
+                let mut environment = HashMap::new();
+
+                for id in closure.captures {
+                    let binding = self.binding(&id)?;
+                    // Constants are copied, mutable variables stay shared with their declaration
+                    let binding = if binding.borrow().mutable {
+                        binding
+                    } else {
+                        Rc::new(RefCell::new(binding.borrow().clone()))
+                    };
+                    environment.insert(id, binding);
+                }
 
-        return_value
+                Ok(Value::Closure {
+                    arguments: closure.arguments,
+                    rest: closure.rest,
+                    instructions: closure.instructions,
+                    environment,
+                    name: closure.name,
+                })
+            }
+            Instruction::ExecuteFunction(id, arguments) => {
+                self.function_call(id, arguments)?.ok_or_else(|| {
+                    RuntimeError::new("Expected a value but the function did not return anything")
+                })
+            }
+            instruction => Err(RuntimeError::new(format!(
+                "Instruction {instruction:?} does not produce a value"
+            ))),
+        }
     }
 
-    fn pop_value(&mut self, id: Id) {
-        //self.memory.remove(&id);
+    /// Evaluates the arguments of a call or the items of a collection, inserting the elements of
+    /// the lists spread into them
+    fn eval_items(&mut self, items: Vec<Value>) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::new();
+
+        for item in items {
+            let Value::LazyEval(instruction) = item else {
+                values.push(self.eval(item)?);
+                continue;
+            };
+
+            match *instruction {
+                Instruction::Spread(list) => match self.eval(list)? {
+                    Value::List(items) => values.extend(items),
+                    value => {
+                        return Err(RuntimeError::new(format!(
+                            "Only a List can be spread, found {}",
+                            value.type_name()
+                        )))
+                    }
+                },
+                instruction => values.push(self.evaluate_instruction(instruction)?),
+            }
+        }
+
+        Ok(values)
     }
 
     // Evaluates non-evaluated Values (function calls, var copies...)
-    fn eval(&mut self, value: Value) -> Value {
+    fn eval(&mut self, value: Value) -> Result<Value, RuntimeError> {
         match value {
             // Value::Closure { instructions } => todo!(),
-            Value::CopyVar(id, _) => {
-                self.shout_memory();
-                self.memory.get(&id).unwrap().clone().value.clone()
-            }
-
-            Value::LazyEval(contents) => self.execute(vec![(*contents.clone())]).unwrap(),
-            value => value,
+            Value::CopyVar(id) => Ok(self.binding(&id)?.borrow().value.clone()),
+            Value::LazyEval(contents) => self.evaluate_instruction(*contents),
+            value => Ok(value),
         }
     }
 
     pub fn shout_memory(&self) {
         println!("| Memory shout!");
-        println!("| {: <40}| {: <12}| {}", "ID", "Mutable", "Value");
-        for (key, entry) in self.memory.clone().into_iter() {
+        println!("| {: <40}| {: <12}| Value", "ID", "Mutable");
+        for binding in self.memory.iter() {
+            let (key, entry) = (binding.key(), binding.value().borrow());
             println!(
                 "| {: <40}| {: <12}| {}",
                 key,
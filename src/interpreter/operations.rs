@@ -0,0 +1,233 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+
+use crate::parsers::operations::{Operator, UnaryOperator};
+
+use super::{RuntimeError, Value};
+
+fn mismatch(operator: Operator, left: &Value, right: &Value) -> RuntimeError {
+    RuntimeError::new(format!(
+        "Cannot apply '{operator}' to {} and {}",
+        left.type_name(),
+        right.type_name()
+    ))
+}
+
+/// Ints are checked, a result that doesn't fit in 64 bits is an error rather than wrapping around
+fn int_operation(operator: Operator, left: i64, right: i64) -> Result<Value, RuntimeError> {
+    let result = match operator {
+        Operator::Addition => left.checked_add(right),
+        Operator::Substraction => left.checked_sub(right),
+        Operator::Multiplication => left.checked_mul(right),
+        Operator::Division => {
+            if right == 0 {
+                return Err(RuntimeError::new("Division by zero"));
+            }
+            left.checked_div(right)
+        }
+        _ => unreachable!("'{operator}' is not an arithmetic operator"),
+    };
+
+    match result {
+        Some(result) => Ok(Value::Int64(result)),
+        None => Err(RuntimeError::new(format!(
+            "Integer overflow: {left} {operator} {right} does not fit in an Int, use big() for \
+             larger numbers"
+        ))),
+    }
+}
+
+fn big_operation(operator: Operator, left: BigInt, right: BigInt) -> Result<Value, RuntimeError> {
+    Ok(Value::BigInt(match operator {
+        Operator::Addition => left + right,
+        Operator::Substraction => left - right,
+        Operator::Multiplication => left * right,
+        Operator::Division => {
+            if right == BigInt::from(0) {
+                return Err(RuntimeError::new("Division by zero"));
+            }
+            left / right
+        }
+        _ => unreachable!("'{operator}' is not an arithmetic operator"),
+    }))
+}
+
+fn float_operation(operator: Operator, left: f64, right: f64) -> Value {
+    Value::Float64(match operator {
+        Operator::Addition => left + right,
+        Operator::Substraction => left - right,
+        Operator::Multiplication => left * right,
+        Operator::Division => left / right,
+        _ => unreachable!("'{operator}' is not an arithmetic operator"),
+    })
+}
+
+/// An Int can only be combined with an Int and a Float with a Float. Ints combined with a BigInt
+/// become BigInts. The `add` builtin follows the same rules
+fn arithmetic(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    match (left, right) {
+        (Value::Int64(left), Value::Int64(right)) => int_operation(operator, left, right),
+        (Value::Float64(left), Value::Float64(right)) => Ok(float_operation(operator, left, right)),
+        (Value::BigInt(left), Value::BigInt(right)) => big_operation(operator, left, right),
+        (Value::BigInt(left), Value::Int64(right)) => {
+            big_operation(operator, left, BigInt::from(right))
+        }
+        (Value::Int64(left), Value::BigInt(right)) => {
+            big_operation(operator, BigInt::from(left), right)
+        }
+        (left, right) => Err(mismatch(operator, &left, &right)),
+    }
+}
+
+/// Ints and Floats can be compared together, BigInts can be compared with Ints, and Strings are
+/// compared alphabetically. The `smaller` builtin follows the same rules for numbers
+fn comparison(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    let ordering = match (&left, &right) {
+        (Value::Int64(left), Value::Int64(right)) => Some(left.cmp(right)),
+        (Value::Float64(left), Value::Float64(right)) => left.partial_cmp(right),
+        (Value::Int64(left), Value::Float64(right)) => (*left as f64).partial_cmp(right),
+        (Value::Float64(left), Value::Int64(right)) => left.partial_cmp(&(*right as f64)),
+        (Value::BigInt(left), Value::BigInt(right)) => Some(left.cmp(right)),
+        (Value::BigInt(left), Value::Int64(right)) => Some(left.cmp(&BigInt::from(*right))),
+        (Value::Int64(left), Value::BigInt(right)) => Some(BigInt::from(*left).cmp(right)),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => return Err(mismatch(operator, &left, &right)),
+    };
+
+    // Comparisons involving NaN are always false
+    let Some(ordering) = ordering else {
+        return Ok(Value::Boolean(false));
+    };
+
+    Ok(Value::Boolean(match operator {
+        Operator::Smaller => ordering == Ordering::Less,
+        Operator::SmallerOrEquals => ordering != Ordering::Greater,
+        Operator::Greater => ordering == Ordering::Greater,
+        Operator::GreaterOrEquals => ordering != Ordering::Less,
+        _ => unreachable!("'{operator}' is not a comparison operator"),
+    }))
+}
+
+/// Follows the same rules as the `equals` builtin. Values of different types are never equal,
+/// except for the numbers that can be compared: `1 == 1.0`
+fn equality(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    let is_equal = left.equals(&right)?;
+
+    Ok(Value::Boolean(match operator {
+        Operator::Equals => is_equal,
+        _ => !is_equal,
+    }))
+}
+
+fn logical(operator: Operator, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    match (&left, &right) {
+        (Value::Boolean(left), Value::Boolean(right)) => Ok(Value::Boolean(match operator {
+            Operator::And => *left && *right,
+            _ => *left || *right,
+        })),
+        _ => Err(mismatch(operator, &left, &right)),
+    }
+}
+
+/// Applies a binary operator to two evaluated values
+pub fn binary_operation(
+    operator: Operator,
+    left: Value,
+    right: Value,
+) -> Result<Value, RuntimeError> {
+    match operator {
+        Operator::Addition
+        | Operator::Substraction
+        | Operator::Multiplication
+        | Operator::Division => arithmetic(operator, left, right),
+        Operator::Smaller
+        | Operator::SmallerOrEquals
+        | Operator::Greater
+        | Operator::GreaterOrEquals => comparison(operator, left, right),
+        Operator::Equals | Operator::NotEquals => equality(operator, left, right),
+        Operator::And | Operator::Or => logical(operator, left, right),
+    }
+}
+
+pub fn unary_operation(operator: UnaryOperator, value: Value) -> Result<Value, RuntimeError> {
+    match (operator, value) {
+        (UnaryOperator::Negation, Value::Int64(value)) => match value.checked_neg() {
+            Some(value) => Ok(Value::Int64(value)),
+            None => Err(RuntimeError::new(format!(
+                "Integer overflow: -({value}) does not fit in an Int, use big() for larger numbers"
+            ))),
+        },
+        (UnaryOperator::Negation, Value::Float64(value)) => Ok(Value::Float64(-value)),
+        (UnaryOperator::Negation, Value::BigInt(value)) => Ok(Value::BigInt(-value)),
+        (UnaryOperator::Not, Value::Boolean(value)) => Ok(Value::Boolean(!value)),
+        (operator, value) => Err(RuntimeError::new(format!(
+            "Cannot apply '{operator}' to {}",
+            value.type_name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn numeric_equality() {
+        let one = [
+            Value::Int64(1),
+            Value::Float64(1.0),
+            Value::BigInt(BigInt::from(1)),
+        ];
+
+        // The numbers that can be compared with `<` are equal when they have the same value
+        for (left, right) in [(&one[0], &one[1]), (&one[0], &one[2])] {
+            for (left, right) in [(left, right), (right, left)] {
+                assert_eq!(
+                    binary_operation(Operator::Equals, left.clone(), right.clone()),
+                    Ok(Value::Boolean(true))
+                );
+                assert_eq!(
+                    binary_operation(Operator::SmallerOrEquals, left.clone(), right.clone()),
+                    Ok(Value::Boolean(true))
+                );
+            }
+        }
+
+        assert_eq!(
+            binary_operation(Operator::NotEquals, Value::Int64(2), Value::Float64(2.5)),
+            Ok(Value::Boolean(true))
+        );
+        assert_eq!(
+            binary_operation(
+                Operator::Equals,
+                Value::Int64(1),
+                Value::String("1".to_string())
+            ),
+            Ok(Value::Boolean(false))
+        );
+    }
+
+    #[test]
+    pub fn overflows() {
+        assert!(
+            binary_operation(Operator::Addition, Value::Int64(i64::MAX), Value::Int64(1)).is_err()
+        );
+        assert!(binary_operation(
+            Operator::Multiplication,
+            Value::Int64(1 << 62),
+            Value::Int64(2)
+        )
+        .is_err());
+        assert!(binary_operation(Operator::Division, Value::Int64(1), Value::Int64(0)).is_err());
+        assert!(unary_operation(UnaryOperator::Negation, Value::Int64(i64::MIN)).is_err());
+        assert_eq!(
+            binary_operation(
+                Operator::Addition,
+                Value::BigInt(BigInt::from(i64::MAX)),
+                Value::Int64(1)
+            ),
+            Ok(Value::BigInt(BigInt::from(i64::MAX) + 1))
+        );
+    }
+}
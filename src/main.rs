@@ -1,11 +1,14 @@
 mod interpreter;
 mod parsers;
 
+use colored::Colorize;
 use interpreter::Compiler;
 use interpreter::Engine;
 
 use std::collections::HashMap;
+use std::env;
 use std::fs::read_to_string;
+use std::path::Path;
 
 use nom::{character, combinator, sequence, IResult};
 
@@ -24,41 +27,37 @@ fn main() {
     let code = "if a -> end";
     conditional_statement(code).unwrap();
 
-    let code = read_to_string("./programs/fib.msq").unwrap();
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "./programs/fib.msq".to_string());
+    let path = Path::new(&path);
 
-    let code = code.clone();
-    let code = &code;
+    let code = match read_to_string(path) {
+        Ok(code) => code,
+        Err(error) => {
+            println!(
+                "{}",
+                format!("Cannot read {}: {error}", path.display()).red()
+            );
+            return;
+        }
+    };
 
     println!("{code}");
 
     let scope = &mut vec![HashMap::new()];
     let mut compiler = Compiler::new(scope);
-    compiler.compile(code);
+    if let Err(error) = compiler.compile_file(path) {
+        println!("{}", error.to_string().red());
+        return;
+    }
     // println!("{:#?}", compiler.instructions.clone());
 
     let mut engine = Engine::new();
-    engine.execute_program(compiler.instructions);
-    engine.shout_memory();
-}
-
-#[cfg(test)]
-pub mod test {
-    use crate::parsers::args_list::args_list;
-
-    #[test]
-    pub fn arguments() {
-        let string = r#"a, b, c"#;
-        let res = args_list(string);
-        println!("{res:?}");
-
-        let string = r#"a"#;
-        let res = args_list(string);
-        println!("{res:?}");
-
-        let string = r#"_"#;
-        let res = args_list(string);
-        println!("{res:?}");
+    if let Err(error) = engine.execute_program(compiler.instructions) {
+        println!("{}", error.to_string().red());
     }
+    engine.shout_memory();
 }
 
 pub fn number(input: &str) -> IResult<&str, &str> {
@@ -86,3 +85,1069 @@ print ...args ->
 end
 
 "*/
+
+#[cfg(test)]
+pub mod test {
+    use std::collections::HashMap;
+
+    use crate::interpreter::{Compiler, Engine, RuntimeError, Value};
+
+    /// Compiles and runs a program, returning what its main function returned
+    fn run(code: &str) -> Result<Option<Value>, RuntimeError> {
+        let scope = &mut vec![HashMap::new()];
+        let mut compiler = Compiler::new(scope);
+        compiler.compile(code).unwrap();
+
+        Engine::new().execute_program(compiler.instructions)
+    }
+
+    /// Whether a program is rejected, either by the compiler or while running
+    fn fails(code: &str) -> bool {
+        let scope = &mut vec![HashMap::new()];
+        let mut compiler = Compiler::new(scope);
+
+        compiler.compile(code).is_err()
+            || Engine::new()
+                .execute_program(compiler.instructions)
+                .is_err()
+    }
+
+    #[test]
+    pub fn arithmetic() {
+        let code = "main -> return 1 + 2 * 3 - 8 / (2 + 2) end";
+        assert_eq!(run(code), Ok(Some(Value::Int64(5))));
+
+        let code = "main -> return -(1.5 * 2.0) end";
+        assert_eq!(run(code), Ok(Some(Value::Float64(-3.0))));
+
+        let code = "main -> return 1 + 2.0 end";
+        assert!(fails(code));
+    }
+
+    #[test]
+    pub fn comparisons() {
+        let code = "main -> return 1 + 1 == 2 and not 3 < 2 or false end";
+        assert_eq!(run(code), Ok(Some(Value::Boolean(true))));
+
+        let code = r#"main -> return "a" != "b" and 2.5 >= 2 end"#;
+        assert_eq!(run(code), Ok(Some(Value::Boolean(true))));
+
+        // Numbers that can be compared are equal when they have the same value
+        let code = "main -> return (1 == 1.0, 2.5 != 2, [1, 2] == [1.0, 2], equals(big(3), 3), 1 == \"1\") end";
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            "(true, true, true, true, false)"
+        );
+
+        // The right side would be a runtime error if it was evaluated
+        let code = "main -> return false and 1 / 0 == 0 end";
+        assert_eq!(run(code), Ok(Some(Value::Boolean(false))));
+    }
+
+    #[test]
+    pub fn conditionals() {
+        let code = "
+        sign n ->
+            if n < 0 -> return -1
+            else if n == 0 -> return 0
+            else return 1
+            end
+        end
+
+        main -> return sign(-5) * 100 + sign(0) * 10 + sign(3) end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(-99))));
+
+        let code = "main -> if 1 -> return 1 end return 0 end";
+        assert!(fails(code));
+    }
+
+    #[test]
+    pub fn loops() {
+        let code = "
+        main ->
+            mut total = 0
+            mut i = 0
+            while true ->
+                i = i + 1
+                let square = i * i
+                if square > 50 -> break end
+                if i == 3 -> continue end
+                total = total + square
+            end
+            return total
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(1 + 4 + 16 + 25 + 36 + 49))));
+
+        let scope = &mut vec![HashMap::new()];
+        let code = "main -> while true -> f -> break end end end";
+        assert!(Compiler::new(scope).compile(code).is_err());
+    }
+
+    #[test]
+    pub fn lists() {
+        let code = "
+        main ->
+            mut grid = [[1, 2], [3, 4]]
+            grid[1][0] = 30
+            push(grid, [5, 6])
+            let last = pop(grid)
+            return [len(grid), grid[1][0], last[1]]
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::List(vec![
+                Value::Int64(2),
+                Value::Int64(30),
+                Value::Int64(6)
+            ])))
+        );
+
+        let code = "main -> let xs = [1, 2, 3] return xs[3] end";
+        assert!(run(code).is_err());
+
+        let code = "main -> let xs = [1] push(xs, 2) return xs end";
+        assert!(run(code).is_err());
+    }
+
+    #[test]
+    pub fn maps() {
+        let code = r#"
+        main ->
+            mut ages = {"bob": 30, "alice": 25}
+            ages["carol"] = 41
+            ages["bob"] = ages["bob"] + 1
+            return [keys(ages), values(ages), has(ages, "dave"), len(ages)]
+        end
+        "#;
+        let names = ["alice", "bob", "carol"].map(|name| Value::String(name.to_string()));
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::List(vec![
+                Value::List(names.to_vec()),
+                Value::List(vec![Value::Int64(25), Value::Int64(31), Value::Int64(41)]),
+                Value::Boolean(false),
+                Value::Int64(3)
+            ])))
+        );
+
+        let code = r#"main -> let m = {1: "one"} return m[1.0] end"#;
+        assert!(run(code).is_err());
+    }
+
+    #[test]
+    pub fn tuples() {
+        let code = "
+        divmod a, b -> return (a / b, a - a / b * b) end
+
+        main ->
+            let (q, r) = divmod(17, 5)
+            let pair = (q, r)
+            return (pair[0] * 10 + pair[1], pair == (3, 2))
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(32),
+                Value::Boolean(true)
+            ])))
+        );
+
+        let code = "main -> let (a, b) = (1, 2, 3) return a end";
+        assert!(run(code).is_err());
+    }
+
+    #[test]
+    pub fn closures() {
+        let code = "
+        apply f, x -> return f(x) end
+
+        twice f ->
+            return x -> return f(f(x)) end
+        end
+
+        main ->
+            let double = x -> return x * 2 end
+            let quadruple = twice(double)
+            let is_done = true
+            if is_done -> return apply(quadruple, 3) + apply(y -> return y end, 1) end
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(13))));
+
+        let code = "main -> let n = 5 return n(1) end";
+        assert!(fails(code));
+
+        let code = "main -> let f = a, b -> return a end return f(1) end";
+        assert!(fails(code));
+    }
+
+    #[test]
+    pub fn captures() {
+        let code = "
+        make_counter ->
+            mut count = 0
+            return _ ->
+                count = count + 1
+                return count
+            end
+        end
+
+        main ->
+            let first = make_counter()
+            let second = make_counter()
+            first()
+            first()
+            return (first(), second())
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![Value::Int64(3), Value::Int64(1)])))
+        );
+
+        let code = "
+        main ->
+            mut base = 1
+            let offset = base
+            let add = x -> return x + base + offset end
+            base = 10
+            return add(100)
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(111))));
+
+        let code = "
+        main ->
+            let step = 2
+            countdown n ->
+                if n <= 0 -> return 0 end
+                return n + countdown(n - step)
+            end
+            let nested = _ -> return y -> return countdown(y) + step end end
+            let inner = nested()
+            return inner(6)
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(14))));
+    }
+
+    #[test]
+    pub fn records() {
+        let code = "
+        type Point -> x, y end
+        type Segment -> from, to end
+
+        main ->
+            mut segment = Segment(Point(0, 0), Point(3, 4))
+            segment.to.x = segment.to.x * 2
+            let points = [segment.from, segment.to]
+            return (points[1].x + segment.to.y, segment.from == Point(0, 0), segment.to == Point(3, 4))
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(10),
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ])))
+        );
+
+        let code = r#"
+        type Person -> name, age end
+        main -> return Person("Ada", 36) end
+        "#;
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            r#"Person { name: "Ada", age: 36 }"#
+        );
+
+        let code = "type Point -> x, y end main -> let p = Point(1, 2) return p.z end";
+        assert!(fails(code));
+
+        let code = "type Point -> x, y end main -> let p = Point(1) return p end";
+        assert!(fails(code));
+    }
+
+    #[test]
+    pub fn enums() {
+        let code = "
+        type Shape = Circle(r) | Rect(w, h) | Empty
+
+        area shape ->
+            match shape ->
+                Circle(r) -> return 3 * r * r
+                Rect(w, h) ->
+                    let result = w * h
+                    return result
+                Empty -> return 0
+            end
+        end
+
+        is_round shape ->
+            match shape ->
+                Circle(_) -> return true
+                _ -> return false
+            end
+        end
+
+        main ->
+            let shapes = [Circle(2), Rect(3, 4), Empty]
+            return (area(shapes[0]) + area(shapes[1]) + area(shapes[2]), is_round(Empty), shapes)
+        end
+        ";
+        let result = run(code).unwrap().unwrap();
+        assert_eq!(
+            result.to_string(),
+            "(24, false, [Circle(2), Rect(3, 4), Empty])"
+        );
+
+        // A pattern followed by an arrow only starts an arm at the indentation of the arms
+        let code = "
+        type Shape = Circle(r) | Rect(w, h) | Empty
+
+        main ->
+            mut f = _ -> 0
+            match Circle(2) ->
+                Circle(r) ->
+                    f = x -> x
+                    twice -> r * 2
+                    match Rect(1, 2) ->
+                        Rect(w, h) -> f = x -> x * twice()
+                        _ -> return 0
+                    end
+                    return f(r)
+                Rect(w, h) -> return w
+                Empty -> return 0
+            end
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(8))));
+
+        let scope = &mut vec![HashMap::new()];
+        let code = "
+        type Shape = Circle(r) | Rect(w, h)
+        main -> match Circle(1) -> Circle(r) -> return r end end
+        ";
+        assert!(Compiler::new(scope).compile(code).is_err());
+
+        let scope = &mut vec![HashMap::new()];
+        let code = "
+        type Shape = Circle(r) | Rect(w, h)
+        main -> match Circle(1) -> Circle(r) -> return r Rect(w) -> return w end end
+        ";
+        assert!(Compiler::new(scope).compile(code).is_err());
+    }
+
+    #[test]
+    pub fn modules() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(directory.join("geometry")).unwrap();
+        let write = |name: &str, code: &str| std::fs::write(directory.join(name), code).unwrap();
+
+        write(
+            "math.msq",
+            "
+            export let pi = 3
+            helper x -> return x * x end
+            export square x -> return helper(x) end
+            ",
+        );
+        write(
+            "geometry/shapes.msq",
+            "
+            import ../math
+            export type Shape = Circle(r) | Square(side)
+            export area shape ->
+                match shape ->
+                    Circle(r) -> return math.pi * math.square(r)
+                    Square(side) -> return math.square(side)
+                end
+            end
+            ",
+        );
+        write(
+            "main.msq",
+            "
+            import math
+            import geometry/shapes
+
+            main -> return shapes.area(shapes.Circle(2)) + math.square(3) end
+            ",
+        );
+        write(
+            "private.msq",
+            "import math main -> return math.helper(2) end",
+        );
+        write("a.msq", "import b main -> end");
+        write("b.msq", "import a");
+
+        let compile = |name: &str| {
+            let scope = &mut vec![HashMap::new()];
+            let mut compiler = Compiler::new(scope);
+            compiler
+                .compile_file(&directory.join(name))
+                .map(|_| compiler.instructions)
+        };
+
+        let instructions = compile("main.msq").unwrap();
+        assert_eq!(
+            Engine::new().execute_program(instructions),
+            Ok(Some(Value::Int64(21)))
+        );
+        assert!(compile("private.msq").is_err());
+        assert_eq!(
+            compile("a.msq").unwrap_err().message,
+            "Cyclic import: a.msq -> b.msq -> a.msq"
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    pub fn variadic() {
+        let code = "
+        count ...items -> return len(items) end
+
+        wrap before, after, ...items ->
+            return [before, ...items, after]
+        end
+
+        main ->
+            let middle = [2, 3]
+            return (count(), count(1, 2, 3), wrap(1, 4, ...middle), wrap(0, 1), add(...middle))
+        end
+        ";
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            "(0, 3, [1, 2, 3, 4], [0, 1], 5)"
+        );
+
+        let code = "wrap a, ...rest -> return rest end main -> return wrap() end";
+        assert!(fails(code));
+    }
+
+    #[test]
+    pub fn strings() {
+        let code = r#"
+        main ->
+            let name = "Ada"
+            let ages = {"Ada": 36}
+            return "Hello {name}, you are {ages[name] + 1}!\n\t\"\\\{\} \u{e9}"
+        end
+        "#;
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::String(
+                "Hello Ada, you are 37!\n\t\"\\{} \u{e9}".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    pub fn tail_calls() {
+        let code = "
+        mut steps = 0
+
+        count n, total ->
+            if n == 0 -> return total end
+            return count(n - 1, total + 1)
+        end
+
+        repeat f, n, x ->
+            if n == 0 -> return x else return repeat(f, n - 1, f(x)) end
+        end
+
+        walk n ->
+            steps = steps + 1
+            if n > 0 -> walk(n - 1) end
+        end
+
+        main ->
+            walk(20000)
+            return (count(20000, 0), repeat(x -> return not x end, 20001, true), steps)
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(20000),
+                Value::Boolean(false),
+                Value::Int64(20001)
+            ])))
+        );
+    }
+
+    #[test]
+    pub fn recursion() {
+        let code = "
+        fib n ->
+            if n < 2 -> return n end
+            return fib(n - 1) + fib(n - 2)
+        end
+
+        digits n ->
+            mut result = []
+            if n >= 10 -> result = digits(n / 10) end
+            push(result, n - n / 10 * 10)
+            return result
+        end
+
+        main -> return (fib(15), digits(1234)) end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(610),
+                Value::List(vec![
+                    Value::Int64(1),
+                    Value::Int64(2),
+                    Value::Int64(3),
+                    Value::Int64(4)
+                ])
+            ])))
+        );
+    }
+
+    #[test]
+    pub fn comments() {
+        let code = r##"# Adds the two numbers
+add a, b -> # trailing comment
+    return a #[ inline ]# + b
+end
+
+#[
+    Multiline block comment
+]#
+main ->
+    let text = "# not a comment {add(1, 2) #[ hidden ]#}"
+    return (add(#[ first ]# 1, 2), text)
+end
+# comment at the end"##;
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(3),
+                Value::String("# not a comment 3".to_string())
+            ])))
+        );
+    }
+
+    #[test]
+    pub fn macros() {
+        let code = "
+        macro swap
+            (a, b) ->
+                let tmp = a
+                a = b
+                b = tmp
+            end
+        end
+
+        macro sum
+            () -> 0 end
+            (a, ...rest) -> a + sum!(...rest) end
+        end
+
+        macro push_unless
+            (condition, list, item) -> if not condition -> push(list, item) end end
+        end
+
+        main ->
+            mut tmp = 1
+            mut other = 2
+            swap!(tmp, other)
+            mut log = []
+            push_unless!(tmp < other, log, sum!(tmp, other, 3))
+            push_unless!(tmp > other, log, 0)
+            return (tmp, other, log)
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(2),
+                Value::Int64(1),
+                Value::List(vec![Value::Int64(6)])
+            ])))
+        );
+
+        // An expansion used as a statement still makes its calls
+        let code = "
+        macro both (a, b) -> [a, b] end end
+
+        increment counter ->
+            deref counter = deref counter + 1
+            return deref counter
+        end
+
+        main ->
+            mut total = 0
+            both!(increment(ref total), increment(ref total))
+            return total
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(2))));
+    }
+
+    #[test]
+    pub fn types() {
+        let code = "
+        type Point -> x, y end
+
+        add_one x: Int -> Int
+            return x + 1
+        end
+
+        double x -> return x * 2 end
+
+        main ->
+            let x: Int = 5
+            let name: String = \"Ada\"
+            let scores: Map[String, Int] = {name: 1}
+            let origin: Point = Point(0, 0)
+            mut items: List[Float] = []
+            push(items, 1.5)
+            let (first, second): (Int, String) = (x, name)
+            return add_one(double(x)) + scores[name] + origin.x
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(12))));
+
+        // A return type is only read before a block, and can be any type name
+        let code = "
+        type Shape = Point | Circle(r)
+        type pair -> left, right end
+
+        swap p: pair -> pair
+            return pair(p.right, p.left)
+        end
+
+        main ->
+            let f = x -> Point
+            let p = swap(pair(1, 2))
+            return (f(0), p.left)
+        end
+        ";
+        assert_eq!(run(code).unwrap().unwrap().to_string(), "(Point, 2)");
+
+        // An untyped parameter can hold a BigInt
+        let code = "
+        double x -> return x * 2 end
+
+        main ->
+            let b: BigInt = double(big(5))
+            return b
+        end
+        ";
+        assert_eq!(run(code).unwrap().unwrap().to_string(), "10");
+
+        let code = "main ->\nmut items: List[Int] = []\npush(items, 1, 2, 3)\nreturn items\nend";
+        assert_eq!(run(code).unwrap().unwrap().to_string(), "[1, 2, 3]");
+    }
+
+    #[test]
+    pub fn results() {
+        let code = "
+        sum a, b ->
+            let total = parse_int(a)? + parse_int(b)?
+            return Ok(total)
+        end
+
+        first items ->
+            return Some(get(items, 0)?)
+        end
+
+        unwrap result ->
+            match result ->
+                Ok(value) -> return value
+                Err(message) -> return message
+            end
+        end
+
+        main ->
+            return (unwrap(sum(\"12\", \"30\")), unwrap(sum(\"12\", \"x\")), first([7]) == Some(7), first([]) == None)
+        end
+        ";
+        let result = run(code).unwrap().unwrap();
+        assert_eq!(
+            result.to_string(),
+            r#"(42, "Cannot parse 'x' as an Int", true, true)"#
+        );
+
+        let scope = &mut vec![HashMap::new()];
+        let error = Compiler::new(scope)
+            .compile("let x = parse_int(\"1\")?\nmain -> return x end")
+            .unwrap_err();
+        assert!(error
+            .message
+            .starts_with("'?' can only be used inside of a function"));
+    }
+
+    #[test]
+    pub fn references() {
+        let code = "
+        increment counter ->
+            deref counter = deref counter + 1
+        end
+
+        fill items, count ->
+            mut index = 0
+            while index < count ->
+                push(items, index)
+                index = index + 1
+            end
+        end
+
+        counter ->
+            mut count = 0
+            return ref count
+        end
+
+        main ->
+            mut total = 1
+            increment(ref total)
+            increment(ref total)
+            mut list = []
+            fill(ref list, 3)
+            let r = ref list
+            deref r[0] = 10
+            let kept = counter()
+            deref kept = 5
+            return (total, list, deref r, deref kept)
+        end
+        ";
+        let result = run(code).unwrap().unwrap();
+        assert_eq!(result.to_string(), "(3, [10, 1, 2], [10, 1, 2], 5)");
+
+        assert!(fails("main -> let x = 1 let r = ref x deref r = 2 end"));
+        assert!(fails(
+            "main -> let items = [] let r = ref items push(r, 1) end"
+        ));
+        assert!(fails("main -> mut items = [] push(items, ref items) end"));
+        assert!(fails("main -> mut a = 0 mut b = ref a a = ref b end"));
+        assert!(fails("main -> let x = deref 5 end"));
+    }
+
+    #[test]
+    pub fn numbers() {
+        let code = "
+        factorial n ->
+            if n == 0 -> return big(1) end
+            return n * factorial(n - 1)
+        end
+
+        main ->
+            let large = 3000000000 * 3
+            let exact = big(\"123456789012345678901234567890\") + 1
+            return (large, factorial(25), exact, 0.1 + 0.2, date() > 1600000000000)
+        end
+        ";
+        let result = run(code).unwrap().unwrap();
+        assert_eq!(
+            result.to_string(),
+            "(9000000000, 15511210043330985984000000, 123456789012345678901234567891, \
+             0.30000000000000004, true)"
+        );
+
+        assert!(fails("main -> return 9223372036854775807 + 1 end"));
+        assert!(fails("main -> return -(-9223372036854775807 - 1) end"));
+        assert!(fails("main -> return 4611686018427387904 * 2 end"));
+        assert!(fails("main -> return 99999999999999999999 end"));
+        assert!(fails("main -> return big(1) + 1.5 end"));
+    }
+
+    #[test]
+    pub fn literals() {
+        let code = "main -> return (-1.5, 1e9, 2.5E-3, 0xFF, 0b1010, 0o17, 1_000_000, -0x10) end";
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            "(-1.5, 1000000000, 0.0025, 255, 10, 15, 1000000, -16)"
+        );
+
+        let errors = [
+            ("main -> return 1 . 5 end", "0: at line 1"),
+            ("main -> return 0b102 end", "malformed number literal"),
+        ];
+
+        for (code, message) in errors {
+            let scope = &mut vec![HashMap::new()];
+            let error = Compiler::new(scope).compile(code).unwrap_err();
+            assert!(error.message.contains(message), "{}", error.message);
+        }
+    }
+
+    #[test]
+    pub fn identifiers() {
+        let code = "
+        ending x -> return x end
+
+        main ->
+            let name_0123456789 = 1
+            let returned = 2
+            mut mutable = 3
+            let letter = 4
+            let größe = 5
+            let trueish = 6
+            let _private = 7
+            mutable = mutable + 1
+            return ending(name_0123456789 + returned + mutable + letter + größe + trueish + _private)
+        end
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(29))));
+
+        assert!(fails("main -> let end = 1 end"));
+        assert!(fails("main -> let 1abc = 1 end"));
+    }
+
+    #[test]
+    pub fn implicit_returns() {
+        let code = "
+        apply f, x -> f(x)
+
+        clamp x ->
+            if x > 10 -> return 10 end
+            x
+        end
+
+        add x -> y -> x + y
+
+        describe x: Int -> String
+            if x < 0 -> return \"negative\" end
+            \"positive\"
+        end
+
+        main ->
+            let double = x -> x * 2
+            let five = -> 5
+            let increment = add(1)
+            let closures = [_ -> 1, x -> x]
+            let identity = closures[1]
+            return (apply(double, 21), clamp(3), clamp(30), increment(2), identity(4), five(), describe(7))
+        end
+        ";
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            r#"(42, 3, 10, 3, 4, 5, "positive")"#
+        );
+    }
+
+    #[test]
+    pub fn closure_arguments() {
+        let code = "
+        apply n, f -> f(n)
+
+        combine a, b, f -> f(a, b)
+
+        main ->
+            mut fs = []
+            let j = 3
+            push(fs, _ -> j)
+            let first = fs[0]
+            return (apply(4, y -> return y * 2 end), first(), combine(1, 2, (a, b -> a + b)))
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Int64(8),
+                Value::Int64(3),
+                Value::Int64(3)
+            ])))
+        );
+
+        // Annotated parameters all belong to the closure
+        let code = "
+        combine a, b, f -> f(a, b)
+
+        main -> combine(1, 2, a: Int, b: Int -> a * 10 + b)
+        ";
+        assert_eq!(run(code), Ok(Some(Value::Int64(12))));
+    }
+
+    #[test]
+    pub fn closure_blocks() {
+        let code = "
+        type Maybe = Something(value) | Nothing
+
+        nothing x -> Nothing
+
+        add x ->
+            return y -> x + y end
+        end
+
+        twice x -> # a block starting after a comment
+            mut total = x
+            total = total + x
+            total
+        end
+
+        main -> mut steps = [nothing(1)]
+            push(steps, twice(2))
+            let increment = add(1)
+            push(steps, increment(2))
+            let double = x -> twice(x)
+            push(steps, double(3))
+            steps
+        end
+        ";
+        assert_eq!(
+            run(code).unwrap().unwrap().to_string(),
+            "[Nothing, 4, 3, 6]"
+        );
+
+        // A value on the line of the arrow is a block when `end` follows it
+        assert!(run("f x -> x\nend\nmain -> print(f(3))\nend").is_ok());
+        assert_eq!(
+            run("f x -> x\nend\nmain -> f(3)\nend"),
+            Ok(Some(Value::Int64(3)))
+        );
+    }
+
+    #[test]
+    pub fn closure_comparisons() {
+        let errors = [
+            ("main ->\nlet f = x -> x\nreturn [f] == [f]\nend", "Cannot compare List values holding a closure"),
+            ("main ->\nlet f = x -> x\nreturn equals(f, f)\nend", "Closures cannot be compared"),
+            ("main ->\nlet f = x -> x\nreturn f == f\nend", "Closures cannot be compared"),
+            ("main ->\nlet f = x -> x\nreturn (1, {\"f\": f}) != (1, {\"f\": f})\nend", "Cannot compare Tuple values holding a closure"),
+            ("type Box = Box(value)\nmain ->\nlet f = x -> x\nreturn equals(Box(1), Box(f))\nend", "Cannot compare Enum values holding a closure"),
+        ];
+
+        for (code, message) in errors {
+            let error = run(code).unwrap_err();
+            assert!(error.message.starts_with(message), "{}", error.message);
+        }
+    }
+
+    #[test]
+    pub fn mutual_recursion() {
+        let code = "
+        main -> (even(50001), idle(50000))
+
+        even n ->
+            if n == 0 -> return true end
+            return odd(n - 1)
+        end
+
+        odd n ->
+            if n == 0 -> return false end
+            return even(n - 1)
+        end
+
+        idle steps ->
+            if steps == 0 -> return \"idle\" end
+            return running(steps - 1)
+        end
+
+        running steps ->
+            if steps == 0 -> return \"running\" end
+            return idle(steps - 1)
+        end
+        ";
+        assert_eq!(
+            run(code),
+            Ok(Some(Value::Tuple(vec![
+                Value::Boolean(false),
+                Value::String("idle".to_string())
+            ])))
+        );
+    }
+
+    #[test]
+    pub fn module_variants() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+        let write = |name: &str, code: &str| std::fs::write(directory.join(name), code).unwrap();
+
+        write(
+            "lights.msq",
+            "
+            export type Light = Green | Red
+            export next light ->
+                match light ->
+                    Green -> return Red
+                    Red -> return Green
+                end
+            end
+            ",
+        );
+        write(
+            "signs.msq",
+            "
+            export type Sign = Stop | Red(reason)
+            export describe sign ->
+                match sign ->
+                    Stop -> return \"stop\"
+                    Red(reason) -> return reason
+                end
+            end
+            ",
+        );
+        write(
+            "main.msq",
+            "
+            import lights
+            import signs
+
+            type Local = Red | Blue
+
+            color light ->
+                match light ->
+                    lights.Green -> return \"green\"
+                    lights.Red -> return \"red\"
+                end
+            end
+
+            main ->
+                let local = match_local(Red)
+                let light: lights.Light = lights.next(lights.Green)
+                let sign: signs.Sign = signs.Red(\"closed\")
+                return (color(light), signs.describe(sign), local)
+            end
+
+            match_local value ->
+                match value ->
+                    Red -> return \"local red\"
+                    Blue -> return \"local blue\"
+                end
+            end
+            ",
+        );
+
+        let scope = &mut vec![HashMap::new()];
+        let mut compiler = Compiler::new(scope);
+        compiler.compile_file(&directory.join("main.msq")).unwrap();
+        assert_eq!(
+            Engine::new()
+                .execute_program(compiler.instructions)
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            r#"("red", "closed", "local red")"#
+        );
+
+        write(
+            "unknown.msq",
+            "
+            import lights
+            main -> let light: lights.Color = lights.Green end
+            ",
+        );
+        let scope = &mut vec![HashMap::new()];
+        let mut compiler = Compiler::new(scope);
+        let error = compiler
+            .compile_file(&directory.join("unknown.msq"))
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "Unknown type 'lights.Color' (in function 'main')"
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
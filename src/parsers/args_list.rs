@@ -1,16 +1,43 @@
 use nom::{
-    branch::alt, bytes::complete::tag, combinator::opt, error::VerboseError,
-    multi::separated_list0, IResult,
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{map, opt},
+    error::VerboseError,
+    multi::separated_list0,
+    sequence::{pair, preceded},
+    IResult,
 };
 
-use super::{identifier::identifier, statements::Statement, value::value, ws::ws};
+use super::{
+    closure::argument_closure,
+    identifier::identifier,
+    statements::Statement,
+    types::{annotation, Type},
+    value::{expression, value},
+    ws::ws,
+};
 
 pub fn list(i: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     separated_list0(tag(","), ws(identifier))(i)
 }
 
+/// A list spread into the items around it: `...args` in `print("> ", ...args)`
+fn spread(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    map(preceded(tag("..."), value), |list| {
+        Statement::Spread(Box::new(list))
+    })(i)
+}
+
+fn argument(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((
+        spread,
+        map(argument_closure, Statement::Closure),
+        expression,
+    ))(i)
+}
+
 pub fn args_call_list(i: &str) -> IResult<&str, Vec<Statement>, VerboseError<&str>> {
-    separated_list0(tag(","), ws(value))(i)
+    separated_list0(tag(","), ws(argument))(i)
 }
 
 pub fn underscore<T>(i: &str) -> IResult<&str, Vec<T>, VerboseError<&str>> {
@@ -25,9 +52,94 @@ pub fn args_list(i: &str) -> IResult<&str, Vec<String>, VerboseError<&str>> {
     Ok((remaining, args))
 }
 
+/// The trailing parameter collecting the extra arguments of a call: `...rest`
+fn rest(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    preceded(tag("..."), identifier)(i)
+}
+
+/// The parameters of a closure, along with its trailing `...rest` parameter if it has one
+pub fn parameters(i: &str) -> IResult<&str, (Vec<String>, Option<String>), VerboseError<&str>> {
+    alt((
+        map(rest, |rest| (Vec::new(), Some(rest))),
+        pair(args_list, opt(preceded(ws(tag(",")), rest))),
+    ))(i)
+}
+
+/// A parameter of a closure, along with the type it is annotated with: `x: Int`
+pub type TypedParameter = (String, Option<Type>);
+
+fn typed_parameter(i: &str) -> IResult<&str, TypedParameter, VerboseError<&str>> {
+    pair(identifier, opt(annotation))(i)
+}
+
+fn typed_list(i: &str) -> IResult<&str, Vec<TypedParameter>, VerboseError<&str>> {
+    separated_list0(tag(","), ws(typed_parameter))(i)
+}
+
+/// The parameters of a closure with their optional types, along with its trailing `...rest`
+/// parameter if it has one
+pub fn typed_parameters(
+    i: &str,
+) -> IResult<&str, (Vec<TypedParameter>, Option<String>), VerboseError<&str>> {
+    alt((
+        map(rest, |rest| (Vec::new(), Some(rest))),
+        pair(
+            map(
+                opt(alt((underscore, typed_list))),
+                Option::unwrap_or_default,
+            ),
+            opt(preceded(ws(tag(",")), rest)),
+        ),
+    ))(i)
+}
+
 pub fn call_list(i: &str) -> IResult<&str, Vec<Statement>, VerboseError<&str>> {
     let (remaining, args) = opt(alt((underscore, args_call_list)))(i)?;
     let args = args.unwrap_or(Vec::new());
 
     Ok((remaining, args))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn arguments() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+
+        assert_eq!(args_list("a, b, c"), Ok(("", names(&["a", "b", "c"]))));
+        assert_eq!(args_list("a"), Ok(("", names(&["a"]))));
+        assert_eq!(args_list("_"), Ok(("", names(&[]))));
+    }
+
+    #[test]
+    pub fn closure_arguments() {
+        let closures = |code| {
+            let (remaining, arguments) = args_call_list(code).unwrap();
+            assert_eq!(remaining, "");
+            arguments
+                .into_iter()
+                .map(|argument| match argument {
+                    Statement::Closure(closure) => Some(closure.arguments),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // An untyped closure only takes the parameter before its arrow
+        assert_eq!(
+            closures("1, a, b -> a"),
+            vec![None, None, Some(vec!["b".to_string()])]
+        );
+        // Parentheses or annotations make all the parameters belong to the closure
+        assert_eq!(
+            closures("1, (a, b -> a)"),
+            vec![None, Some(vec!["a".to_string(), "b".to_string()])]
+        );
+        assert_eq!(
+            closures("1, a: Int, b: Int -> a"),
+            vec![None, Some(vec!["a".to_string(), "b".to_string()])]
+        );
+    }
+}
@@ -1,17 +1,17 @@
-use nom::{branch::alt, bytes::complete::tag, error::VerboseError, IResult};
+use nom::{branch::alt, error::VerboseError, IResult};
 
-use super::{statements::Statement, ws::ws};
+use super::{identifier::keyword, statements::Statement};
 
-fn True(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, _) = tag("true")(i)?;
+fn true_value(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = keyword("true")(i)?;
     Ok((remaining, Statement::Boolean(true)))
 }
 
-fn False(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, _) = tag("false")(i)?;
+fn false_value(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = keyword("false")(i)?;
     Ok((remaining, Statement::Boolean(false)))
 }
 
 pub fn boolean(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    alt((True, False))(i)
+    alt((true_value, false_value))(i)
 }
@@ -1,44 +1,283 @@
-use crate::parsers::args_list::args_list;
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::parsers::args_list::{typed_parameters, TypedParameter};
 use nom::{
     branch::alt,
-    bytes::complete::{tag, tag_no_case, take},
-    character::complete::char,
-    error::VerboseError,
+    bytes::complete::tag,
+    character::complete::{line_ending, space0},
+    combinator::{eof, map, not, opt, peek, success, verify},
+    error::{ErrorKind, ParseError, VerboseError},
+    sequence::{delimited, pair, preceded, terminated},
     IResult,
 };
+use uuid::Uuid;
 
 use super::{
-    statements::{statement, statements, Statements},
+    comment::blank,
+    identifier::keyword,
+    statements::{statements, Statement, Statements},
+    types::{type_annotation, Type},
+    value::value,
     ws::ws,
 };
 
+/// The value ending a closure is returned. A call is left as it is, the tail call it becomes
+/// already returns its value
+fn implicit_return(value: Statement) -> Statement {
+    match value {
+        Statement::FunctionCall(_) => value,
+        value => Statement::Return(Box::new(value)),
+    }
+}
+
+/// A body made of a single value on the line of the arrow: `x -> x + 1`. The value has to end the
+/// line, or the list, map or call the closure is written in, and can't be followed by `end`
 fn one_statement(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
-    let (remaining, statement) = statement(i)?;
+    let (remaining, value) = preceded(pair(space0, not(alt((line_ending, tag("#"))))), value)(i)?;
+    peek(pair(
+        space0,
+        alt((
+            eof,
+            line_ending,
+            tag("#"),
+            tag(")"),
+            tag(","),
+            tag("]"),
+            tag("}"),
+        )),
+    ))(remaining)?;
+    not(ws(keyword("end")))(remaining)?;
+
     Ok((
         remaining,
         Statements {
-            body: vec![statement],
+            body: vec![implicit_return(value)],
         },
     ))
 }
 
-fn multiple_statements(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
-    let (remaining, statements) = ws(statements)(i)?;
-    let (remaining, _) = ws(tag("end"))(remaining)?;
+/// A block parsed at a position of the program, by the length of the code left after it, or
+/// `None` when there is no block at that position
+type Block = Option<(usize, Statements)>;
+
+thread_local! {
+    /// The blocks parsed in the program being parsed, by the length of the code left at their
+    /// position. A body on the line of the arrow is parsed as a block before being parsed as a
+    /// single value, the bodies following it would otherwise be parsed again for each attempt
+    static BLOCKS: RefCell<Option<HashMap<usize, Block>>> = const { RefCell::new(None) };
+}
+
+/// Runs the parsing of a whole program, remembering the blocks parsed at each of its positions
+pub fn remembering_blocks<T>(parse: impl FnOnce() -> T) -> T {
+    BLOCKS.with(|blocks| *blocks.borrow_mut() = Some(HashMap::new()));
+    let parsed = parse();
+    BLOCKS.with(|blocks| *blocks.borrow_mut() = None);
+    parsed
+}
+
+fn block(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
+    let (remaining, (mut statements, last)) =
+        terminated(pair(ws(statements), opt(ws(value))), ws(keyword("end")))(i)?;
+    statements.body.extend(last.map(implicit_return));
     Ok((remaining, statements))
 }
 
+/// A body ending with `end`, its last statement can be a value that is returned
+fn multiple_statements(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
+    let remembered = BLOCKS.with(|blocks| {
+        blocks
+            .borrow()
+            .as_ref()
+            .and_then(|blocks| blocks.get(&i.len()).cloned())
+    });
+
+    let parsed = match remembered {
+        Some(Some((left, statements))) => return Ok((&i[i.len() - left..], statements)),
+        Some(None) => {
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                i,
+                ErrorKind::Verify,
+            )))
+        }
+        None => block(i),
+    };
+
+    let block = match &parsed {
+        Ok((remaining, statements)) => Some((remaining.len(), statements.clone())),
+        Err(nom::Err::Error(_)) => None,
+        Err(_) => return parsed,
+    };
+    BLOCKS.with(|blocks| {
+        if let Some(blocks) = blocks.borrow_mut().as_mut() {
+            blocks.insert(i.len(), block);
+        }
+    });
+    parsed
+}
+
+/// The body of a closure, a single value on the line of the arrow or a block. A value followed by
+/// `end` starts a block, so that `x -> x` followed by `end` on the next line returns `x`
+fn body(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
+    alt((one_statement, multiple_statements))(i)
+}
+
+/// The body of a declared function, which is a block whenever an `end` closes it:
+/// `main -> print("a")` followed by more statements and `end`
+fn declared_body(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
+    alt((multiple_statements, one_statement))(i)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Closure {
     pub arguments: Vec<String>,
+    /// The types the arguments are annotated with, in the same order
+    pub types: Vec<Option<Type>>,
+    /// The parameter collecting the extra arguments into a list: `rest` in `a, ...rest ->`
+    pub rest: Option<String>,
     pub body: Statements,
+    /// The type the closure is annotated to return
+    pub returns: Option<Type>,
+    /// Identifies the closure, the memory slots of its arguments are derived from it
+    pub id: String,
+}
+
+/// The return type following the arrow of a declared function, it has to end its line: `-> Int`
+fn return_type(i: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    delimited(
+        space0,
+        type_annotation,
+        peek(pair(space0, alt((line_ending, tag("#"))))),
+    )(i)
+}
+
+/// The block following a return type, it can't be empty so that `x -> x` followed by `end`
+/// returns `x`
+fn typed_body(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
+    verify(multiple_statements, |body: &Statements| {
+        !body.body.is_empty()
+    })(i)
+}
+
+/// The arrow and body following the parameters of a closure, along with the return type of a
+/// declared function
+fn closure_rest(
+    i: &str,
+    (parameters, rest): (Vec<TypedParameter>, Option<String>),
+    declared: bool,
+) -> IResult<&str, Closure, VerboseError<&str>> {
+    let (remaining, _) = tag("->")(i)?;
+    // Only a declared function has a return type and a body that is a block whenever `end` closes
+    // it, `let f = x -> g(x)` followed by statements and `end` stays a closure returning `g(x)`
+    let (remaining, (returns, body)) = match declared {
+        true => alt((
+            pair(map(return_type, Some), typed_body),
+            pair(success(None), declared_body),
+        ))(remaining)?,
+        false => pair(success(None), body)(remaining)?,
+    };
+    let (arguments, types) = parameters.into_iter().unzip();
+
+    Ok((
+        remaining,
+        Closure {
+            arguments,
+            types,
+            rest,
+            body,
+            returns,
+            id: Uuid::new_v4().to_string(),
+        },
+    ))
 }
 
 pub fn closure(i: &str) -> IResult<&str, Closure, VerboseError<&str>> {
-    let (remaining, arguments) = ws(args_list)(i)?;
-    let (remaining, _) = ws(tag("->"))(remaining)?;
-    let (remaining, body) = multiple_statements(remaining)?;
-    //let (remaining, body) = /*alt((*/multiple_statements/*, one_statement))*/(remaining)?;
+    let (remaining, parameters) = ws(typed_parameters)(i)?;
+    closure_rest(remaining, parameters, false)
+}
+
+/// The closure of a function declaration, which can be annotated with a return type:
+/// `add_one x: Int -> Int`
+pub fn declared_closure(i: &str) -> IResult<&str, Closure, VerboseError<&str>> {
+    let (remaining, parameters) = ws(typed_parameters)(i)?;
+    closure_rest(remaining, parameters, true)
+}
+
+/// Whether the parameters before an arrow in a call all belong to the closure passed as an
+/// argument. They are read as a unit, but plain names before the last one are arguments of the
+/// call: `apply(n, y -> y * 2)` passes `n` and a closure taking `y`. A list starting with an
+/// annotated parameter can't be arguments, so `fold(xs, 0, total: Int, x: Int -> total + x)`
+/// passes a closure taking both
+fn argument_parameters((parameters, rest): &(Vec<TypedParameter>, Option<String>)) -> bool {
+    parameters.len() + usize::from(rest.is_some()) <= 1
+        || parameters
+            .first()
+            .is_some_and(|(_, annotation)| annotation.is_some())
+}
+
+/// A closure passed as an argument
+pub fn argument_closure(i: &str) -> IResult<&str, Closure, VerboseError<&str>> {
+    let (remaining, parameters) =
+        delimited(blank, verify(typed_parameters, argument_parameters), blank)(i)?;
+    closure_rest(remaining, parameters, false)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsers::program::program;
+
+    fn returned(name: &str) -> Statement {
+        Statement::Return(Box::new(Statement::Variable(name.to_string())))
+    }
+
+    #[test]
+    pub fn closure_values() {
+        // A value on the line of the arrow is the body, unless `end` follows it
+        let (remaining, parsed) = closure("x -> x\ny").unwrap();
+        assert_eq!((remaining, parsed.body.body), ("\ny", vec![returned("x")]));
+
+        let (remaining, parsed) = closure("x -> x\nend\ny").unwrap();
+        assert_eq!((remaining, parsed.body.body), ("y", vec![returned("x")]));
+
+        let (remaining, parsed) = closure("x -> Point, 1").unwrap();
+        assert_eq!(
+            (remaining, parsed.body.body, parsed.returns),
+            (", 1", vec![returned("Point")], None)
+        );
+    }
+
+    #[test]
+    pub fn declared_bodies() {
+        // A declared function's body is a block whenever `end` closes it
+        let (remaining, parsed) = declared_closure("-> print(1)\nx\nend").unwrap();
+        assert_eq!(remaining, "");
+        assert_eq!(parsed.body.body.len(), 2);
+
+        let (remaining, parsed) = declared_closure("p: pair -> pair\np\nend").unwrap();
+        assert_eq!(
+            (remaining, parsed.body.body, parsed.returns),
+            (
+                "",
+                vec![returned("p")],
+                Some(Type::Named("pair".to_string()))
+            )
+        );
+
+        // A return type is followed by a block that isn't empty, so this returns `pair`
+        let (remaining, parsed) = declared_closure("x -> pair\nend").unwrap();
+        assert_eq!(
+            (remaining, parsed.body.body, parsed.returns),
+            ("", vec![returned("pair")], None)
+        );
+    }
 
-    Ok((remaining, Closure { arguments, body }))
+    #[test]
+    pub fn remembered_blocks() {
+        // Each body on the line of an arrow is parsed once, whatever the number of them
+        let code = (0..40)
+            .map(|i| format!("f{i} x -> print(x)\n"))
+            .collect::<String>();
+        let (_, parsed) = program(&code).unwrap();
+        assert_eq!(parsed.statements.body.len(), 40);
+    }
 }
@@ -0,0 +1,138 @@
+use std::{cell::RefCell, collections::BTreeMap, ops::Range};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_until},
+    character::complete::{char, multispace1, not_line_ending},
+    combinator::{cut, map, recognize, value},
+    error::{ContextError, ParseError},
+    multi::many0,
+    sequence::{pair, preceded, terminated},
+    IResult, Offset,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+/// A comment of a program, along with its position in the source
+pub struct Comment {
+    pub text: String,
+    /// The byte offsets of the comment in the source
+    pub span: Range<usize>,
+}
+
+/// A `# ...` comment running until the end of the line, or a `#[ ... ]#` block comment
+pub fn comment<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, &'a str, E> {
+    alt((
+        recognize(preceded(
+            tag("#["),
+            cut(terminated(take_until("]#"), tag("]#"))),
+        )),
+        recognize(pair(char('#'), not_line_ending)),
+    ))(i)
+}
+
+thread_local! {
+    /// The comments read in the program being parsed, by the length of the code left at their
+    /// position. The parsers backtrack, so a comment can be read several times
+    static COMMENTS: RefCell<Option<BTreeMap<usize, String>>> = const { RefCell::new(None) };
+}
+
+/// Runs the parsing of a whole program, collecting the comments read by `blank` along with their
+/// position in the program
+pub fn collecting_comments<T>(code: &str, parse: impl FnOnce() -> T) -> (T, Vec<Comment>) {
+    COMMENTS.with(|comments| *comments.borrow_mut() = Some(BTreeMap::new()));
+    let parsed = parse();
+    let comments = COMMENTS
+        .with(|comments| comments.borrow_mut().take())
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|(left, text)| {
+            let start = code.len() - left;
+            Comment {
+                span: start..start + text.len(),
+                text,
+            }
+        })
+        .collect();
+
+    (parsed, comments)
+}
+
+/// Any amount of whitespace and comments, along with the comments read
+pub fn blank<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
+    i: &'a str,
+) -> IResult<&'a str, Vec<&'a str>, E> {
+    let (remaining, blanks) = many0(alt((value(None, multispace1), map(comment, Some))))(i)?;
+    let comments: Vec<&str> = blanks.into_iter().flatten().collect();
+
+    COMMENTS.with(|read| {
+        if let Some(read) = read.borrow_mut().as_mut() {
+            for comment in &comments {
+                read.insert(i.len() - i.offset(comment), comment.to_string());
+            }
+        }
+    });
+    Ok((remaining, comments))
+}
+
+#[cfg(test)]
+mod test {
+    use nom::error::VerboseError;
+
+    use super::*;
+
+    #[test]
+    pub fn comments() {
+        assert_eq!(
+            comment::<VerboseError<&str>>("# line\nnext"),
+            Ok(("\nnext", "# line"))
+        );
+        assert_eq!(
+            comment::<VerboseError<&str>>("#[ block\n]# next"),
+            Ok((" next", "#[ block\n]#"))
+        );
+        assert!(matches!(
+            comment::<VerboseError<&str>>("#[ unterminated"),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    pub fn blanks() {
+        assert_eq!(
+            blank::<VerboseError<&str>>("  # a\n\t#[ b ]# x"),
+            Ok(("x", vec!["# a", "#[ b ]#"]))
+        );
+        assert_eq!(blank::<VerboseError<&str>>("x"), Ok(("x", vec![])));
+    }
+
+    #[test]
+    pub fn collected_comments() {
+        let code = "a # first\n#[ second ]# b";
+        let (_, comments) = collecting_comments(code, || {
+            // Reading the same comments again doesn't collect them twice
+            blank::<VerboseError<&str>>(&code[1..]).unwrap();
+            blank::<VerboseError<&str>>(&code[1..]).unwrap()
+        });
+
+        assert_eq!(
+            comments,
+            vec![
+                Comment {
+                    text: "# first".to_string(),
+                    span: 2..9,
+                },
+                Comment {
+                    text: "#[ second ]#".to_string(),
+                    span: 10..22,
+                },
+            ]
+        );
+
+        // Comments are only collected while parsing a program
+        blank::<VerboseError<&str>>(" # ignored").unwrap();
+        assert_eq!(collecting_comments(code, || ()).1, vec![]);
+    }
+}
@@ -1,14 +1,12 @@
 use nom::{
-    bytes::complete::tag,
-    combinator::opt,
-    error::{VerboseError, VerboseErrorKind},
+    bytes::complete::tag, combinator::opt, error::VerboseError, multi::many0, sequence::pair,
     IResult,
 };
 
 use super::{
-    closure::Closure,
-    statements::{statement, statements, Statement, Statements},
-    value::value,
+    identifier::keyword,
+    statements::{statements, Statement, Statements},
+    value::expression,
     ws::ws,
 };
 
@@ -23,28 +21,38 @@ fn arrow(i: &str) -> IResult<&str, &str, VerboseError<&str>> {
     ws(tag("->"))(i)
 }
 
-//fn else_if(i: &str) -> IResult<&str, (Statement, Statements), VerboseError<&str>> {}
+/// Parses a condition and the body that runs when it is true: `condition -> body`
+fn branch(i: &str) -> IResult<&str, (Statement, Statements), VerboseError<&str>> {
+    let (remaining, condition) = ws(expression)(i)?;
+    let (remaining, _) = arrow(remaining)?;
+    let (remaining, body) = opt(ws(statements))(remaining)?;
+    let body = body.unwrap_or(Statements { body: Vec::new() });
+
+    Ok((remaining, (condition, body)))
+}
+
+fn else_if(i: &str) -> IResult<&str, (Statement, Statements), VerboseError<&str>> {
+    let (remaining, _) = pair(ws(keyword("else")), ws(keyword("if")))(i)?;
+    branch(remaining)
+}
 
 fn else_parser(i: &str) -> IResult<&str, Statements, VerboseError<&str>> {
-    let (remaining, _) = ws(tag("else"))(i)?;
+    let (remaining, _) = ws(keyword("else"))(i)?;
     statements(remaining)
 }
 
 pub fn conditional_statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, _) = ws(tag("if"))(i)?;
-    let (remaining, condition) = ws(value)(remaining)?;
-    let (remaining, _) = arrow(remaining)?;
-    let (remaining, body) = opt(ws(statements))(remaining)?;
-    let body = body.unwrap_or(Statements { body: Vec::new() });
-
+    let (remaining, _) = ws(keyword("if"))(i)?;
+    let (remaining, main_condition) = branch(remaining)?;
+    let (remaining, alternates) = many0(else_if)(remaining)?;
     let (remaining, fallback) = opt(else_parser)(remaining)?;
-    let (remaining, _) = ws(tag("end"))(remaining)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
 
     Ok((
         remaining,
         Statement::Conditional(Conditional {
-            main_condition: Box::new((condition, body)),
-            alternates: Vec::new(),
+            main_condition: Box::new(main_condition),
+            alternates,
             fallback,
         }),
     ))
@@ -0,0 +1,54 @@
+use nom::{
+    bytes::complete::tag, combinator::opt, error::VerboseError, multi::separated_list1,
+    sequence::delimited, IResult,
+};
+use uuid::Uuid;
+
+use super::{
+    args_list::list,
+    identifier::{identifier, keyword},
+    statements::Statement,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<String>,
+    /// The id of the constructor of the variant
+    pub id: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDeclaration {
+    pub name: String,
+    pub variants: Vec<Variant>,
+}
+
+/// A variant with its fields: `Rect(w, h)`, or `Empty` when it doesn't hold anything
+fn variant(i: &str) -> IResult<&str, Variant, VerboseError<&str>> {
+    let (remaining, name) = identifier(i)?;
+    let (remaining, fields) = opt(delimited(tag("("), ws(list), tag(")")))(remaining)?;
+
+    Ok((
+        remaining,
+        Variant {
+            name,
+            fields: fields.unwrap_or_default(),
+            id: Uuid::new_v4().to_string(),
+        },
+    ))
+}
+
+/// A sum type: `type Shape = Circle(r) | Rect(w, h)`
+pub fn enum_declaration(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("type"))(i)?;
+    let (remaining, name) = ws(identifier)(remaining)?;
+    let (remaining, _) = ws(tag("="))(remaining)?;
+    let (remaining, variants) = separated_list1(tag("|"), ws(variant))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::EnumDeclaration(EnumDeclaration { name, variants }),
+    ))
+}
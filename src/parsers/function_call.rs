@@ -1,21 +1,31 @@
-use nom::{bytes::complete::tag, error::VerboseError, sequence::delimited, IResult};
+use nom::{
+    bytes::complete::tag, combinator::opt, error::VerboseError, sequence::delimited, IResult,
+};
 
-use super::{args_list::call_list, identifier::identifier, statements::Statement, ws::ws};
+use super::{
+    args_list::call_list, identifier::identifier, record::field, statements::Statement, ws::ws,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionCall {
+    /// The name of the function, prefixed by its namespace for imported ones: `math.square`
     pub name: String,
     pub arguments: Vec<Statement>,
 }
 
 pub fn function_call(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
     let (remaining, name) = ws(identifier)(i)?;
+    let (remaining, member) = opt(field)(remaining)?;
+    let name = match member {
+        Some(member) => format!("{name}.{member}"),
+        None => name,
+    };
     let (remaining, args) = delimited(tag("("), ws(call_list), tag(")"))(remaining)?;
 
     Ok((
         remaining,
         Statement::FunctionCall(FunctionCall {
-            name: String::from(name),
+            name,
             arguments: args,
         }),
     ))
@@ -1,11 +1,8 @@
-use nom::{
-    error::{ParseError, VerboseError},
-    IResult, Parser,
-};
+use nom::{error::VerboseError, IResult};
 use uuid::Uuid;
 
 use super::{
-    closure::{closure, Closure},
+    closure::{declared_closure, Closure},
     identifier::identifier,
     statements::Statement,
     ws::ws,
@@ -20,7 +17,7 @@ pub struct FunctionDeclaration {
 
 pub fn function_declaration(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
     let (remaining, name) = ws(identifier)(i)?;
-    let (remaining, closure) = ws(closure)(remaining)?;
+    let (remaining, closure) = ws(declared_closure)(remaining)?;
     Ok((
         remaining,
         Statement::FunctionDeclaration(FunctionDeclaration {
@@ -1,14 +1,69 @@
 use nom::{
-    bytes::complete::tag,
-    combinator::{not, peek},
-    error::VerboseError,
+    bytes::complete::{tag, take_while},
+    character::complete::satisfy,
+    combinator::{not, recognize},
+    error::{ErrorKind, ParseError, VerboseError},
+    sequence::{pair, terminated},
     IResult,
 };
+use unicode_xid::UnicodeXID;
 
+/// The words with a meaning of their own, they can't be used as names
+const KEYWORDS: [&str; 21] = [
+    "and", "break", "continue", "deref", "else", "end", "export", "false", "if", "import", "let",
+    "macro", "match", "mut", "not", "or", "ref", "return", "true", "type", "while",
+];
+
+/// Whether a character can continue a name: a letter, a digit or an underscore, in any script
+fn is_name_character(c: char) -> bool {
+    c.is_xid_continue()
+}
+
+/// A letter or an underscore followed by letters, digits and underscores: `name_0123456789`,
+/// `größe`. Keywords only match as whole words, so `ending` is a name
 pub fn identifier(i: &str) -> IResult<&str, String, VerboseError<&str>> {
-    peek(not(tag("end")))(i)?;
-    peek(not(tag("return")))(i)?;
-    let (remaining, id) =
-        nom::bytes::complete::is_a("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_")(i)?;
-    Ok((remaining, id.to_string()))
+    let (remaining, name) = recognize(pair(
+        satisfy(|c| c == '_' || c.is_xid_start()),
+        take_while(is_name_character),
+    ))(i)?;
+
+    if KEYWORDS.contains(&name) {
+        return Err(nom::Err::Error(VerboseError::from_error_kind(
+            i,
+            ErrorKind::Tag,
+        )));
+    }
+
+    Ok((remaining, name.to_string()))
+}
+
+/// Matches `word` only if it is not directly followed by another identifier character,
+/// so that `or` doesn't match the start of `order`
+pub fn keyword<'a>(
+    word: &'static str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, VerboseError<&'a str>> {
+    move |i: &'a str| terminated(tag(word), not(satisfy(is_name_character)))(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn identifiers() {
+        for name in ["name_0123456789", "größe", "_private", "ending", "trueish"] {
+            assert_eq!(identifier(name), Ok(("", name.to_string())));
+        }
+
+        assert_eq!(identifier("a-b"), Ok(("-b", "a".to_string())));
+        assert!(identifier("end").is_err());
+        assert!(identifier("1abc").is_err());
+    }
+
+    #[test]
+    pub fn keywords() {
+        assert_eq!(keyword("or")("or b"), Ok((" b", "or")));
+        assert!(keyword("or")("order").is_err());
+        assert!(keyword("end")("end_of_list").is_err());
+    }
 }
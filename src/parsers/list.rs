@@ -0,0 +1,31 @@
+use nom::{
+    branch::alt, bytes::complete::tag, combinator::map, error::VerboseError, multi::many0,
+    sequence::delimited, IResult,
+};
+
+use super::{
+    args_list::args_call_list, record::field, statements::Statement, value::value, ws::ws,
+};
+
+/// What follows a value to reach one of its elements
+#[derive(Debug, Clone, PartialEq)]
+pub enum Accessor {
+    /// `[0]` in `xs[0]`
+    Index(Statement),
+    /// `.x` in `point.x`
+    Field(String),
+}
+
+/// A list literal: `[1, 2, 3]`
+pub fn list(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, items) = delimited(tag("["), ws(args_call_list), tag("]"))(i)?;
+    Ok((remaining, Statement::List(items)))
+}
+
+/// The accessors following a value: `[i][j]` in `grid[i][j]`, or `[0].x` in `points[0].x`
+pub fn accessors(i: &str) -> IResult<&str, Vec<Accessor>, VerboseError<&str>> {
+    many0(alt((
+        map(delimited(tag("["), ws(value), tag("]")), Accessor::Index),
+        map(field, Accessor::Field),
+    )))(i)
+}
@@ -0,0 +1,92 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    error::VerboseError,
+    multi::{many0, many1},
+    sequence::{delimited, terminated},
+    IResult,
+};
+
+use super::{
+    args_list::{call_list, parameters},
+    comment::blank,
+    identifier::{identifier, keyword},
+    statements::{statement, Statement},
+    value::value,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroRule {
+    pub parameters: Vec<String>,
+    /// The parameter collecting the extra arguments of the call: `rest` in `(a, ...rest)`
+    pub rest: Option<String>,
+    /// The statements replacing the call, a call used as a value has to expand to a single one
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDeclaration {
+    pub name: String,
+    /// The rules of the macro, the first one accepting the arguments of a call is expanded
+    pub rules: Vec<MacroRule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroCall {
+    pub name: String,
+    pub arguments: Vec<Statement>,
+    /// The length of the source from the call to its end, locates the call in errors
+    pub position: usize,
+}
+
+/// `(a, b) -> body end`, the body can also be a lone value: `(a) -> a * a end`
+fn rule(i: &str) -> IResult<&str, MacroRule, VerboseError<&str>> {
+    let (remaining, (parameters, rest)) = delimited(tag("("), ws(parameters), tag(")"))(i)?;
+    let (remaining, _) = ws(tag("->"))(remaining)?;
+    let (remaining, body) = many0(ws(body_item))(remaining)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
+
+    Ok((
+        remaining,
+        MacroRule {
+            parameters,
+            rest,
+            body,
+        },
+    ))
+}
+
+fn body_item(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((statement, value))(i)
+}
+
+/// `macro square (x) -> x * x end end`
+pub fn macro_declaration(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("macro"))(i)?;
+    let (remaining, name) = ws(identifier)(remaining)?;
+    let (remaining, rules) = many1(ws(rule))(remaining)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::MacroDeclaration(MacroDeclaration { name, rules }),
+    ))
+}
+
+/// `square!(4)`, there can't be any space before the exclamation mark
+pub fn macro_call(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (start, _) = blank(i)?;
+    let (remaining, name) = terminated(identifier, char('!'))(start)?;
+    let (remaining, arguments) = delimited(tag("("), ws(call_list), tag(")"))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::MacroCall(MacroCall {
+            name,
+            arguments,
+            position: start.len(),
+        }),
+    ))
+}
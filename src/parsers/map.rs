@@ -0,0 +1,19 @@
+use nom::{
+    bytes::complete::tag,
+    error::VerboseError,
+    multi::separated_list0,
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+
+use super::{statements::Statement, value::value, ws::ws};
+
+fn entries(i: &str) -> IResult<&str, Vec<(Statement, Statement)>, VerboseError<&str>> {
+    separated_list0(tag(","), separated_pair(ws(value), tag(":"), ws(value)))(i)
+}
+
+/// A map literal: `{"a": 1, "b": 2}`
+pub fn map(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, entries) = delimited(tag("{"), ws(entries), tag("}"))(i)?;
+    Ok((remaining, Statement::Map(entries)))
+}
@@ -0,0 +1,193 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{map, not, opt},
+    error::{ErrorKind, ParseError, VerboseError},
+    multi::many0,
+    sequence::{delimited, pair, preceded},
+    IResult,
+};
+use uuid::Uuid;
+
+use super::{
+    args_list::list,
+    comment::blank,
+    identifier::{identifier, keyword},
+    record::field,
+    statements::{statement, Statement, Statements},
+    value::expression,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    /// The variant handled by the arm, `None` for the `_` arm handling every other variant. It is
+    /// prefixed by its namespace for the variants of imported modules
+    pub variant: Option<String>,
+    /// The names the values of the variant are bound to, along with their ids
+    pub names: Vec<String>,
+    pub ids: Vec<String>,
+    pub body: Statements,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub value: Box<Statement>,
+    pub arms: Vec<MatchArm>,
+}
+
+/// The name of a variant, prefixed by its namespace for imported ones: `shapes.Circle`
+fn variant(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    let (remaining, name) = identifier(i)?;
+    let (remaining, member) = opt(field)(remaining)?;
+    Ok((
+        remaining,
+        match member {
+            Some(member) => format!("{name}.{member}"),
+            None => name,
+        },
+    ))
+}
+
+/// `Rect(w, h)`, `Empty`, `shapes.Circle(r)` or `_`
+fn pattern(i: &str) -> IResult<&str, (Option<String>, Vec<String>), VerboseError<&str>> {
+    alt((
+        map(pair(tag("_"), not(identifier)), |_| (None, Vec::new())),
+        map(
+            pair(variant, opt(delimited(tag("("), ws(list), tag(")")))),
+            |(variant, names)| (Some(variant), names.unwrap_or_default()),
+        ),
+    ))(i)
+}
+
+fn arm_head(i: &str) -> IResult<&str, (Option<String>, Vec<String>), VerboseError<&str>> {
+    let (remaining, pattern) = ws(pattern)(i)?;
+    let (remaining, _) = preceded(blank, tag("->"))(remaining)?;
+    Ok((remaining, pattern))
+}
+
+/// The indentation of the code at `position` when it starts its line, `code` being the code
+/// following the arrow of the match it is in
+fn indentation(code: &str, position: &str) -> Option<usize> {
+    let (_, line) = code[..code.len() - position.len()].rsplit_once('\n')?;
+    line.chars().all(char::is_whitespace).then_some(line.len())
+}
+
+/// Whether the next arm starts after the blanks at the start of the input. A pattern followed by
+/// an arrow on a line more indented than the arms is a statement of the current arm, so that
+/// `twice -> r * 2` declares a function in its body
+fn next_arm<'a>(
+    i: &'a str,
+    code: &str,
+    arms: Option<usize>,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    let (remaining, _) = blank(i)?;
+    if let (Some(arms), Some(line)) = (arms, indentation(code, remaining)) {
+        if line > arms {
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                i,
+                ErrorKind::Verify,
+            )));
+        }
+    }
+
+    map(arm_head, |_| ())(remaining)
+}
+
+/// An arm runs until the next one starts, or until the end of the match. `indentation` is the one
+/// of the arms when they are written on their own lines
+fn arm<'a>(
+    i: &'a str,
+    code: &str,
+    indentation: Option<usize>,
+) -> IResult<&'a str, MatchArm, VerboseError<&'a str>> {
+    let (remaining, (variant, names)) = arm_head(i)?;
+    let (remaining, body) = many0(preceded(
+        not(|i| next_arm(i, code, indentation)),
+        ws(statement),
+    ))(remaining)?;
+
+    let ids = names.iter().map(|_| Uuid::new_v4().to_string()).collect();
+
+    Ok((
+        remaining,
+        MatchArm {
+            variant,
+            names,
+            ids,
+            body: Statements { body },
+        },
+    ))
+}
+
+/// `match shape -> Circle(r) -> ... Rect(w, h) -> ... end`
+pub fn match_statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("match"))(i)?;
+    let (remaining, value) = ws(expression)(remaining)?;
+    let (code, _) = tag("->")(remaining)?;
+    let (first, _) = blank(code)?;
+    let arms = indentation(code, first);
+    let (remaining, arms) = many0(|i| arm(i, code, arms))(code)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::Match(Match {
+            value: Box::new(value),
+            arms,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The variants of the arms of a match, along with the number of statements of their body
+    fn arms(code: &str) -> Vec<(Option<String>, usize)> {
+        let (remaining, Statement::Match(parsed)) = match_statement(code).unwrap() else {
+            panic!("{code} is not a match");
+        };
+        assert_eq!(remaining.trim(), "");
+
+        parsed
+            .arms
+            .into_iter()
+            .map(|arm| (arm.variant, arm.body.body.len()))
+            .collect()
+    }
+
+    #[test]
+    pub fn arms_on_one_line() {
+        assert_eq!(
+            arms("match shape -> shapes.Circle(r) -> return r _ -> return 0 end"),
+            vec![(Some("shapes.Circle".to_string()), 1), (None, 1)]
+        );
+    }
+
+    #[test]
+    pub fn arm_indentation() {
+        // A pattern followed by an arrow only starts an arm at the indentation of the arms
+        let code = "match shape ->
+            Circle(r) ->
+                f = x -> x
+                twice -> r * 2
+                match Rect(1, 2) ->
+                    Rect(w, h) -> f = x -> x * twice()
+                    _ -> return 0
+                end
+                return f(r)
+            Rect(w, h) -> return w
+            Empty ->
+                return 0
+        end";
+        assert_eq!(
+            arms(code),
+            vec![
+                (Some("Circle".to_string()), 4),
+                (Some("Rect".to_string()), 1),
+                (Some("Empty".to_string()), 1)
+            ]
+        );
+    }
+}
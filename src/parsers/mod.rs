@@ -1,16 +1,27 @@
 pub mod args_list;
 pub mod boolean;
 pub mod closure;
+pub mod comment;
 pub mod conditional;
-mod function_call;
+pub mod enumeration;
+pub mod function_call;
 pub mod function_declaration;
 pub mod identifier;
+pub mod list;
+pub mod macros;
+pub mod map;
+pub mod match_statement;
+pub mod module;
 pub mod number;
 pub mod operations;
 pub mod program;
+pub mod record;
 pub mod return_statement;
 pub mod statements;
 pub mod string;
+pub mod tuple;
+pub mod types;
 mod value;
 pub mod variable;
+pub mod while_loop;
 pub mod ws;
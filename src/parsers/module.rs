@@ -0,0 +1,48 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::map,
+    error::VerboseError,
+    multi::{many0, separated_list1},
+    sequence::terminated,
+    IResult,
+};
+
+use super::{
+    enumeration::enum_declaration,
+    function_declaration::function_declaration,
+    identifier::{identifier, keyword},
+    record::type_declaration,
+    statements::Statement,
+    tuple::destructuring,
+    variable::variable,
+    ws::ws,
+};
+
+/// `import math`, `import geometry/shapes` for a file in a sub directory or `import ../math` for
+/// one in the parent directory. The path is relative to the importing file and doesn't include
+/// the `.msq` extension
+pub fn import(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("import"))(i)?;
+    let (remaining, mut path) =
+        many0(map(terminated(tag(".."), char('/')), String::from))(remaining)?;
+    let (remaining, names) = separated_list1(char('/'), identifier)(remaining)?;
+    path.extend(names);
+
+    Ok((remaining, Statement::Import(path)))
+}
+
+/// A top level declaration visible to the files importing the module: `export square x -> ... end`
+pub fn export(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("export"))(i)?;
+    let (remaining, declaration) = alt((
+        destructuring,
+        type_declaration,
+        enum_declaration,
+        function_declaration,
+        variable,
+    ))(remaining)?;
+
+    Ok((remaining, Statement::Export(Box::new(declaration))))
+}
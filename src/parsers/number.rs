@@ -1,43 +1,190 @@
 use nom::{
     branch::alt,
-    bytes::complete::{is_a, tag},
-    combinator::opt,
-    error::VerboseError,
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, one_of},
+    combinator::{opt, recognize},
+    error::{VerboseError, VerboseErrorKind},
+    multi::separated_list1,
+    sequence::pair,
     IResult,
 };
 
-use super::{statements::Statement, ws::ws};
+use super::statements::Statement;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Number {
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    Float(f64),
 }
 
-fn int(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, result) = opt(tag("-"))(i)?;
+/// An error stopping the parsing of a literal that starts like a number but isn't a valid one
+fn malformed<'a>(i: &'a str, message: &'static str) -> nom::Err<VerboseError<&'a str>> {
+    nom::Err::Failure(VerboseError {
+        errors: vec![(i, VerboseErrorKind::Context(message))],
+    })
+}
 
-    let sign = result.unwrap_or("");
+/// Digits in a base, which can be separated by single underscores: `1_000_000`. The underscores
+/// are removed
+fn digits<'a>(radix: u32) -> impl Fn(&'a str) -> IResult<&'a str, String, VerboseError<&'a str>> {
+    move |i: &'a str| {
+        let (remaining, digits) = recognize(separated_list1(
+            char('_'),
+            take_while1(|c: char| c.is_digit(radix)),
+        ))(i)?;
 
-    let (remaining, result) = is_a("0123456789")(remaining)?;
-    let result = format!("{sign}{result}");
-    let number = result.parse::<i32>().unwrap();
+        Ok((remaining, digits.replace('_', "")))
+    }
+}
 
-    Ok((remaining, Statement::Number(Number::Int(number))))
+/// Makes sure a literal isn't directly followed by a letter, a digit or an underscore, so that
+/// `12abc`, `1__000` or `0b102` are errors rather than a number followed by something else
+fn end<'a>(i: &str, start: &'a str) -> Result<(), nom::Err<VerboseError<&'a str>>> {
+    if i.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        return Err(malformed(start, "malformed number literal"));
+    }
+    Ok(())
 }
 
-fn float(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, whole_part) = is_a("0123456789")(i)?;
-    let (remaining, _) = ws(tag("."))(remaining)?;
-    let (remaining, decimal_part) = is_a("0123456789")(remaining)?;
+fn int<'a>(
+    text: &str,
+    radix: u32,
+    start: &'a str,
+) -> Result<Number, nom::Err<VerboseError<&'a str>>> {
+    match i64::from_str_radix(text, radix) {
+        Ok(number) => Ok(Number::Int(number)),
+        Err(_) => Err(malformed(start, "integer literal does not fit in an Int")),
+    }
+}
 
-    let str_rep = format!("{}.{}", whole_part, decimal_part);
+/// A hexadecimal, binary or octal integer: `0xFF`, `0b1010`, `0o755`
+fn radix_int(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, sign) = opt(char('-'))(i)?;
+    let (remaining, prefix) = alt((tag("0x"), tag("0b"), tag("0o")))(remaining)?;
 
-    let number = str_rep.parse::<f32>().unwrap();
+    let radix = match prefix {
+        "0x" => 16,
+        "0b" => 2,
+        _ => 8,
+    };
 
-    Ok((remaining, Statement::Number(Number::Float(number))))
+    let (remaining, digits) = digits(radix)(remaining)
+        .map_err(|_| malformed(i, "expected digits after the prefix of the number"))?;
+    end(remaining, i)?;
+
+    let text = format!("{}{digits}", sign.map_or("", |_| "-"));
+    Ok((remaining, Statement::Number(int(&text, radix, i)?)))
+}
+
+/// A decimal number, it is a Float when it has a fractional part or an exponent: `-1.5`, `1e9`,
+/// `2.5E-3`
+fn decimal(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, sign) = opt(char('-'))(i)?;
+    let (remaining, whole) = digits(10)(remaining)?;
+    let mut text = format!("{}{whole}", sign.map_or("", |_| "-"));
+    let mut is_float = false;
+
+    let (mut remaining, dot) = opt(char('.'))(remaining)?;
+    if dot.is_some() {
+        let (rest, fraction) = digits(10)(remaining)
+            .map_err(|_| malformed(i, "expected digits after the decimal point"))?;
+        text = format!("{text}.{fraction}");
+        is_float = true;
+        remaining = rest;
+    }
+
+    let (rest, exponent) = opt(one_of("eE"))(remaining)?;
+    remaining = rest;
+    if exponent.is_some() {
+        let (rest, (sign, exponent)) = pair(opt(one_of("+-")), digits(10))(remaining)
+            .map_err(|_| malformed(i, "expected digits in the exponent of the number"))?;
+        text = format!("{text}e{}{exponent}", sign.unwrap_or('+'));
+        is_float = true;
+        remaining = rest;
+    }
+
+    end(remaining, i)?;
+
+    let number = if is_float {
+        match text.parse::<f64>() {
+            Ok(number) if number.is_finite() => Number::Float(number),
+            _ => return Err(malformed(i, "float literal does not fit in a Float")),
+        }
+    } else {
+        int(&text, 10, i)?
+    };
+
+    Ok((remaining, Statement::Number(number)))
 }
 
 pub fn number(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    alt((float, int))(i)
+    alt((radix_int, decimal))(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The number a literal is parsed into
+    fn parsed(i: &str) -> Number {
+        match number(i) {
+            Ok(("", Statement::Number(number))) => number,
+            result => panic!("{i} is not a number: {result:?}"),
+        }
+    }
+
+    /// The message of the error stopping the parsing of a malformed literal
+    fn malformation(i: &str) -> &'static str {
+        match number(i) {
+            Err(nom::Err::Failure(error)) => match error.errors[0].1 {
+                VerboseErrorKind::Context(message) => message,
+                _ => panic!("{i} failed without a message"),
+            },
+            result => panic!("{i} is not a malformed number: {result:?}"),
+        }
+    }
+
+    #[test]
+    pub fn literals() {
+        let numbers = [
+            ("-1.5", Number::Float(-1.5)),
+            ("1e9", Number::Float(1e9)),
+            ("2.5E-3", Number::Float(0.0025)),
+            ("0xFF", Number::Int(255)),
+            ("0b1010", Number::Int(10)),
+            ("0o17", Number::Int(15)),
+            ("1_000_000", Number::Int(1_000_000)),
+            ("-0x10", Number::Int(-16)),
+            ("9223372036854775807", Number::Int(i64::MAX)),
+        ];
+
+        for (literal, number) in numbers {
+            assert_eq!(parsed(literal), number);
+        }
+    }
+
+    #[test]
+    pub fn malformed_literals() {
+        let errors = [
+            ("1__000", "malformed number literal"),
+            ("0b102", "malformed number literal"),
+            ("12abc", "malformed number literal"),
+            ("0x", "expected digits after the prefix of the number"),
+            ("1.", "expected digits after the decimal point"),
+            ("1e", "expected digits in the exponent of the number"),
+            ("1e999", "float literal does not fit in a Float"),
+            (
+                "0x8000000000000000",
+                "integer literal does not fit in an Int",
+            ),
+            (
+                "99999999999999999999",
+                "integer literal does not fit in an Int",
+            ),
+        ];
+
+        for (literal, message) in errors {
+            assert_eq!(malformation(literal), message);
+        }
+    }
 }
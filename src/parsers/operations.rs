@@ -1,11 +1,245 @@
-use nom::{error::VerboseError, IResult};
+use std::fmt;
 
-use super::statements::Statement;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, not},
+    error::VerboseError,
+    multi::fold_many0,
+    sequence::{pair, preceded, terminated},
+    IResult,
+};
 
-enum Operation {
-    Add(Statement, Statement),
-    Substraction(Statement, Statement),
-    Equals(Statement, Statement),
+use super::{
+    comment::blank,
+    identifier::{identifier, keyword},
+    statements::Statement,
+    value::operand,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Addition,
+    Substraction,
+    Multiplication,
+    Division,
+    Equals,
+    NotEquals,
+    Smaller,
+    SmallerOrEquals,
+    Greater,
+    GreaterOrEquals,
+    And,
+    Or,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Addition => "+",
+            Operator::Substraction => "-",
+            Operator::Multiplication => "*",
+            Operator::Division => "/",
+            Operator::Equals => "==",
+            Operator::NotEquals => "!=",
+            Operator::Smaller => "<",
+            Operator::SmallerOrEquals => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEquals => ">=",
+            Operator::And => "and",
+            Operator::Or => "or",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Negation,
+    Not,
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOperator::Negation => write!(f, "-"),
+            UnaryOperator::Not => write!(f, "not"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub operator: Operator,
+    pub left: Statement,
+    pub right: Statement,
+}
+
+fn additive(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    alt((
+        map(char('+'), |_| Operator::Addition),
+        // The closure arrow also starts with a minus
+        map(terminated(char('-'), not(char('>'))), |_| {
+            Operator::Substraction
+        }),
+    ))(i)
 }
 
-// fn addition(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {}
+fn comparative(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    alt((
+        map(tag("=="), |_| Operator::Equals),
+        map(tag("!="), |_| Operator::NotEquals),
+        map(tag("<="), |_| Operator::SmallerOrEquals),
+        map(tag(">="), |_| Operator::GreaterOrEquals),
+        map(char('<'), |_| Operator::Smaller),
+        map(char('>'), |_| Operator::Greater),
+    ))(i)
+}
+
+fn conjunctive(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    map(keyword("and"), |_| Operator::And)(i)
+}
+
+fn disjunctive(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    map(keyword("or"), |_| Operator::Or)(i)
+}
+
+fn multiplicative(i: &str) -> IResult<&str, Operator, VerboseError<&str>> {
+    alt((
+        map(char('*'), |_| Operator::Multiplication),
+        map(char('/'), |_| Operator::Division),
+    ))(i)
+}
+
+/// Parses a chain of `next` separated by the operators recognized by `operator`,
+/// folding it into left associative operations: `a - b - c` is `(a - b) - c`
+fn left_associative<'a, O, N>(
+    operator: O,
+    next: N,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Statement, VerboseError<&'a str>>
+where
+    O: Fn(&'a str) -> IResult<&'a str, Operator, VerboseError<&'a str>> + Copy + 'a,
+    N: Fn(&'a str) -> IResult<&'a str, Statement, VerboseError<&'a str>> + Copy,
+{
+    move |i: &'a str| {
+        let (remaining, first) = next(i)?;
+
+        fold_many0(
+            pair(ws(operator), next),
+            move || first.clone(),
+            |left, (operator, right)| {
+                Statement::Operation(Box::new(Operation {
+                    operator,
+                    left,
+                    right,
+                }))
+            },
+        )(remaining)
+    }
+}
+
+fn unary(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((
+        map(
+            preceded(terminated(keyword("ref"), blank), identifier),
+            Statement::Reference,
+        ),
+        map(
+            preceded(terminated(keyword("deref"), blank), unary),
+            |value| Statement::Dereference(Box::new(value)),
+        ),
+        operand,
+        map(pair(char('-'), unary), |(_, value)| {
+            Statement::UnaryOperation(UnaryOperator::Negation, Box::new(value))
+        }),
+    ))(i)
+}
+
+fn product(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    left_associative(multiplicative, unary)(i)
+}
+
+/// Lowest precedence level of arithmetic expressions, `*` and `/` bind tighter than `+` and `-`
+fn sum(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    left_associative(additive, product)(i)
+}
+
+fn comparison(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    left_associative(comparative, sum)(i)
+}
+
+fn negation(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((
+        map(pair(ws(keyword("not")), negation), |(_, value)| {
+            Statement::UnaryOperation(UnaryOperator::Not, Box::new(value))
+        }),
+        comparison,
+    ))(i)
+}
+
+fn conjunction(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    left_associative(conjunctive, negation)(i)
+}
+
+/// Lowest precedence level of all expressions: `or` binds looser than `and`, which binds looser
+/// than `not` and the comparisons
+pub fn disjunction(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    left_associative(disjunctive, conjunction)(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An expression with parentheses around each of its operations
+    fn grouped(i: &str) -> String {
+        fn write(statement: &Statement) -> String {
+            match statement {
+                Statement::Operation(operation) => format!(
+                    "({} {} {})",
+                    write(&operation.left),
+                    operation.operator,
+                    write(&operation.right)
+                ),
+                Statement::UnaryOperation(operator, value) => {
+                    format!("({operator} {})", write(value))
+                }
+                Statement::Variable(name) => name.clone(),
+                statement => format!("{statement:?}"),
+            }
+        }
+
+        let (remaining, expression) = disjunction(i).unwrap();
+        assert_eq!(remaining, "");
+        write(&expression)
+    }
+
+    #[test]
+    pub fn precedence() {
+        assert_eq!(
+            grouped("a + b * c - d / (e + f)"),
+            "((a + (b * c)) - (d / (e + f)))"
+        );
+        assert_eq!(grouped("a - b - c"), "((a - b) - c)");
+        assert_eq!(grouped("-a * b"), "((- a) * b)");
+        assert_eq!(
+            grouped("a + b == c and d < e"),
+            "(((a + b) == c) and (d < e))"
+        );
+        assert_eq!(
+            grouped("not a < b and c or d"),
+            "(((not (a < b)) and c) or d)"
+        );
+        assert_eq!(grouped("order or andy"), "(order or andy)");
+    }
+
+    #[test]
+    pub fn arrows() {
+        assert_eq!(
+            disjunction("a -> a"),
+            Ok((" -> a", Statement::Variable("a".to_string())))
+        );
+    }
+}
@@ -1,53 +1,95 @@
-use std::fmt::Error;
-
-use nom::{
-    combinator::{all_consuming, eof},
-    error::VerboseError,
-    Finish, IResult,
-};
-
-use crate::{parsers::function_declaration::function_declaration, BoxError};
+use nom::{combinator::all_consuming, error::VerboseError, sequence::terminated, IResult};
 
 use super::{
-    function_declaration::FunctionDeclaration,
+    closure::remembering_blocks,
+    comment::{blank, collecting_comments, Comment},
     statements::{statements, Statement, Statements},
 };
 
 #[derive(Debug, Clone)]
 pub struct Program {
     pub main_id: String,
-    functions: Vec<FunctionDeclaration>,
     pub statements: Statements,
+    /// The comments of the program, kept for the tools that need to preserve them
+    pub comments: Vec<Comment>,
 }
 
-pub fn end_of_file(i: &str) -> IResult<&str, &str> {
-    eof(i)
-}
-
-pub fn program<'a>(i: &'a str) -> IResult<&'a str, Program, VerboseError<&str>> {
+pub fn program(i: &str) -> IResult<&str, Program, VerboseError<&str>> {
     //let i = i.clone().as_str();
     let mut program = Program {
         main_id: "".to_string(),
-        functions: Vec::new(),
         statements: Statements { body: Vec::new() },
+        comments: Vec::new(),
     };
 
     // println!("Input {i}");
-    let (remaining, mut statements) = all_consuming(statements)(i)?;
+    let (parsed, comments) = collecting_comments(i, || {
+        remembering_blocks(|| all_consuming(terminated(statements, blank))(i))
+    });
+    let (remaining, mut statements) = parsed?;
+    program.comments = comments;
     // println!("Result: {statements:?}");
     // println!("Rem: {remaining}");
     program.statements.body.append(&mut statements.body);
 
     for statement in program.statements.body.iter() {
-        match statement {
-            Statement::FunctionDeclaration(declaration) => {
-                if declaration.name == "main".to_string() {
-                    program.main_id = declaration.id.clone();
-                }
+        if let Statement::FunctionDeclaration(declaration) = statement {
+            if declaration.name == "main" {
+                program.main_id = declaration.id.clone();
             }
-            _ => {}
         }
     }
 
     Ok((remaining, program))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn comments() {
+        let code = r##"# Adds the two numbers
+add a, b -> # trailing comment
+    return a #[ inline ]# + b
+end
+
+#[
+    Multiline block comment
+]#
+main ->
+    let text = "# not a comment {add(1, 2) #[ hidden ]#}"
+    return (add(#[ first ]# 1, 2), text)
+end
+# comment at the end"##;
+
+        let comments = program(code).unwrap().1.comments;
+        let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "# Adds the two numbers",
+                "# trailing comment",
+                "#[ inline ]#",
+                "#[\n    Multiline block comment\n]#",
+                "#[ hidden ]#",
+                "#[ first ]#",
+                "# comment at the end"
+            ]
+        );
+        assert!(comments
+            .iter()
+            .all(|comment| code[comment.span.clone()] == comment.text));
+    }
+
+    #[test]
+    pub fn main_function() {
+        let (_, parsed) = program("helper -> 1\nmain -> helper()").unwrap();
+        let Statement::FunctionDeclaration(main) = &parsed.statements.body[1] else {
+            panic!("main is not a function declaration");
+        };
+        assert_eq!(parsed.main_id, main.id);
+
+        assert!(program("main -> 1 )").is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use nom::{
+    bytes::complete::tag, character::complete::char, error::VerboseError, sequence::preceded,
+    IResult,
+};
+use uuid::Uuid;
+
+use super::{
+    args_list::list,
+    identifier::{identifier, keyword},
+    statements::Statement,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeDeclaration {
+    pub name: String,
+    pub fields: Vec<String>,
+    /// The id of the constructor of the type
+    pub id: String,
+}
+
+/// A record type: `type Point -> x, y end`
+pub fn type_declaration(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("type"))(i)?;
+    let (remaining, name) = ws(identifier)(remaining)?;
+    let (remaining, _) = ws(tag("->"))(remaining)?;
+    let (remaining, fields) = ws(list)(remaining)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::TypeDeclaration(TypeDeclaration {
+            name,
+            fields,
+            id: Uuid::new_v4().to_string(),
+        }),
+    ))
+}
+
+/// Access to the field of a record: `.x` in `point.x`, there can't be any space around the dot
+pub fn field(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    preceded(char('.'), identifier)(i)
+}
@@ -1,29 +1,21 @@
 use nom::{
-    bytes::complete::tag,
-    error::{ErrorKind, VerboseError, VerboseErrorKind},
+    error::{VerboseError, VerboseErrorKind},
     IResult,
 };
 
-use super::{
-    statements::{statement, Statement},
-    value::value,
-    ws::ws,
-};
+use super::{identifier::keyword, statements::Statement, value::value, ws::ws};
 
 pub fn return_statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let (remaining, _) = ws(tag("return"))(i)?;
+    let (remaining, _) = ws(keyword("return"))(i)?;
     let (remaining, returned) = value(remaining)?;
 
-    match returned {
-        Statement::Return(_) => {
-            return Err(nom::Err::Error(VerboseError {
-                errors: vec![(
-                    remaining,
-                    VerboseErrorKind::Context("Cannot return a return statement"),
-                )],
-            }))
-        }
-        _ => {}
+    if let Statement::Return(_) = returned {
+        return Err(nom::Err::Error(VerboseError {
+            errors: vec![(
+                remaining,
+                VerboseErrorKind::Context("Cannot return a return statement"),
+            )],
+        }));
     }
 
     Ok((remaining, Statement::Return(Box::new(returned))))
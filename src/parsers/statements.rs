@@ -1,13 +1,24 @@
-use nom::{branch::alt, combinator::all_consuming, error::VerboseError, multi::many0, IResult};
+use nom::{
+    branch::alt, character::complete::char, combinator::map, error::VerboseError, multi::many0,
+    sequence::terminated, IResult,
+};
 
 use super::{
     closure::Closure,
     conditional::{conditional_statement, Conditional},
+    enumeration::{enum_declaration, EnumDeclaration},
     function_call::{function_call, FunctionCall},
     function_declaration::{function_declaration, FunctionDeclaration},
+    macros::{macro_call, macro_declaration, MacroCall, MacroDeclaration},
+    match_statement::{match_statement, Match},
+    module::{export, import},
     number::Number,
+    operations::{Operation, UnaryOperator},
+    record::{type_declaration, TypeDeclaration},
     return_statement::return_statement,
+    tuple::{destructuring, Destructuring},
     variable::{variable, Assignement},
+    while_loop::{break_statement, continue_statement, while_loop, WhileLoop},
     ws::ws,
 };
 
@@ -22,19 +33,76 @@ pub enum Statement {
     FunctionCall(FunctionCall),
     FunctionDeclaration(FunctionDeclaration),
     String(String),
+    /// A string with interpolated values, made of its text parts and its values in order
+    Interpolation(Vec<Statement>),
     Closure(Closure),
     Variable(String),
     Number(Number),
     Boolean(bool),
     Return(Box<Statement>),
     Conditional(Conditional),
+    Operation(Box<Operation>),
+    UnaryOperation(UnaryOperator, Box<Statement>),
+    While(WhileLoop),
+    List(Vec<Statement>),
+    /// A map literal, holding its keys and values
+    Map(Vec<(Statement, Statement)>),
+    Tuple(Vec<Statement>),
+    Destructuring(Destructuring),
+    /// Access to an element of a list: `list[index]`
+    Index(Box<Statement>, Box<Statement>),
+    TypeDeclaration(TypeDeclaration),
+    /// Access to the field of a record: `point.x`
+    Field(Box<Statement>, String),
+    EnumDeclaration(EnumDeclaration),
+    Match(Match),
+    /// A list spread into the arguments of a call, or the items of a list or tuple: `...args`
+    Spread(Box<Statement>),
+    /// The path of an imported file, relative to the importing one
+    Import(Vec<String>),
+    Export(Box<Statement>),
+    MacroDeclaration(MacroDeclaration),
+    /// A call to a macro, replaced by the expansion of the macro before being compiled
+    MacroCall(MacroCall),
+    /// The value of an `Ok` or a `Some`, an `Err` or a `None` is returned from the function
+    /// instead: `parse_int(text)?`
+    Try(Box<Statement>),
+    /// A reference to a variable, it reads and modifies the variable itself rather than a copy of
+    /// its value: `ref x`
+    Reference(String),
+    /// The value of the variable a reference points to: `deref r`
+    Dereference(Box<Statement>),
+    Break,
+    Continue,
+}
+
+/// A call whose `Err` or `None` is returned from the function, used for its side effects
+fn propagation(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    map(
+        terminated(alt((macro_call, function_call)), char('?')),
+        |call| Statement::Try(Box::new(call)),
+    )(i)
 }
 
 pub fn statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
     alt((
+        import,
+        export,
         conditional_statement,
+        match_statement,
+        while_loop,
+        macro_declaration,
+        break_statement,
+        continue_statement,
         return_statement,
+        // Before function calls, as `let (a, b)` also looks like a call to `let`
+        destructuring,
+        // Before function declarations, as `type Point -> ...` also looks like a function `type`
+        type_declaration,
+        enum_declaration,
         function_declaration,
+        propagation,
+        macro_call,
         function_call,
         variable,
     ))(i)
@@ -1,18 +1,147 @@
 use nom::{
     branch::alt,
-    bytes::complete::{escaped, tag},
-    character::complete::none_of,
-    error::VerboseError,
-    sequence::delimited,
+    bytes::complete::{tag, take_while_m_n},
+    character::complete::{char, none_of},
+    combinator::{cut, map, map_opt},
+    error::{context, VerboseError},
+    multi::{fold_many1, many0},
+    sequence::{delimited, preceded, terminated},
     IResult,
 };
 
-use super::statements::Statement::{self};
+use super::{
+    statements::Statement::{self},
+    value::value,
+    ws::ws,
+};
+
+/// A part of a string literal, either text or an interpolated value
+enum Part {
+    Text(String),
+    Value(Statement),
+}
+
+/// `\u{1F600}`, the code point of a character in hexadecimal
+fn unicode(i: &str) -> IResult<&str, char, VerboseError<&str>> {
+    map_opt(
+        delimited(
+            tag("u{"),
+            take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+            char('}'),
+        ),
+        |code| u32::from_str_radix(code, 16).ok().and_then(char::from_u32),
+    )(i)
+}
+
+fn escape(i: &str) -> IResult<&str, char, VerboseError<&str>> {
+    preceded(
+        char('\\'),
+        cut(context(
+            "invalid escape sequence",
+            alt((
+                map(char('n'), |_| '\n'),
+                map(char('t'), |_| '\t'),
+                map(char('r'), |_| '\r'),
+                map(char('\\'), |_| '\\'),
+                map(char('"'), |_| '"'),
+                map(char('{'), |_| '{'),
+                map(char('}'), |_| '}'),
+                unicode,
+            )),
+        )),
+    )(i)
+}
+
+fn text(i: &str) -> IResult<&str, String, VerboseError<&str>> {
+    fold_many1(
+        alt((escape, none_of("\\\"{"))),
+        String::new,
+        |mut text, character| {
+            text.push(character);
+            text
+        },
+    )(i)
+}
+
+/// `{name}` in `"Hello {name}"`, a literal brace is written `\{`
+fn interpolation(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    preceded(
+        char('{'),
+        cut(context(
+            "invalid interpolation",
+            terminated(ws(value), char('}')),
+        )),
+    )(i)
+}
 
+/// A string literal. Strings containing interpolated values become `Statement::Interpolation`
 pub fn string(input: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let esc = escaped(none_of("\\\""), '\\', tag("\""));
-    let esc_or_empty = alt((esc, tag("")));
-    let (remaining, res) = delimited(tag("\""), esc_or_empty, tag("\""))(input)?;
+    let (remaining, parts) = delimited(
+        char('"'),
+        many0(alt((
+            map(text, Part::Text),
+            map(interpolation, Part::Value),
+        ))),
+        char('"'),
+    )(input)?;
+
+    if parts.iter().all(|part| matches!(part, Part::Text(_))) {
+        let content = parts
+            .into_iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(text),
+                Part::Value(_) => None,
+            })
+            .collect();
+
+        return Ok((remaining, Statement::String(content)));
+    }
+
+    let parts = parts
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => Statement::String(text),
+            Part::Value(value) => value,
+        })
+        .collect();
+
+    Ok((remaining, Statement::Interpolation(parts)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn escapes() {
+        assert_eq!(
+            string(r#""a\n\t\r\"\\\{\} \u{e9} # b""#),
+            Ok(("", Statement::String("a\n\t\r\"\\{} é # b".to_string())))
+        );
+        assert_eq!(string(r#""""#), Ok(("", Statement::String(String::new()))));
+
+        assert!(matches!(string(r#""\q""#), Err(nom::Err::Failure(_))));
+        assert!(matches!(
+            string(r#""\u{110000}""#),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+
+    #[test]
+    pub fn interpolations() {
+        assert_eq!(
+            string(r#""Hello { name }!""#),
+            Ok((
+                "",
+                Statement::Interpolation(vec![
+                    Statement::String("Hello ".to_string()),
+                    Statement::Variable("name".to_string()),
+                    Statement::String("!".to_string()),
+                ])
+            ))
+        );
 
-    Ok((remaining, Statement::String(String::from(res))))
+        assert!(matches!(string(r#""{1 +}""#), Err(nom::Err::Failure(_))));
+        assert!(matches!(string(r#""{name""#), Err(nom::Err::Failure(_))));
+    }
 }
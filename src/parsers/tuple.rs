@@ -0,0 +1,67 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::opt,
+    error::VerboseError,
+    sequence::{delimited, preceded, terminated},
+    IResult,
+};
+use uuid::Uuid;
+
+use super::{
+    args_list::{args_call_list, list},
+    comment::blank,
+    identifier::keyword,
+    statements::Statement,
+    types::{annotation, Type},
+    value::value,
+    ws::ws,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+/// A declaration unpacking a tuple into several variables: `let (q, r) = divmod(x, y)`
+pub struct Destructuring {
+    pub names: Vec<String>,
+    pub ids: Vec<String>,
+    pub mutable: bool,
+    /// The type of the destructured tuple: `(Int, Int)` in `let (q, r): (Int, Int) = ...`
+    pub annotation: Option<Type>,
+    pub value: Box<Statement>,
+}
+
+/// A tuple literal, it needs a comma to not be confused with parentheses: `(a, b)` or `(a,)`
+pub fn tuple(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = tag("(")(i)?;
+    let (remaining, first) = ws(value)(remaining)?;
+    let (remaining, _) = tag(",")(remaining)?;
+    let (remaining, rest) = args_call_list(remaining)?;
+    let (remaining, _) = opt(tag(","))(remaining)?;
+    let (remaining, _) = preceded(blank, tag(")"))(remaining)?;
+
+    let mut items = vec![first];
+    items.extend(rest);
+
+    Ok((remaining, Statement::Tuple(items)))
+}
+
+pub fn destructuring(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = blank(i)?;
+    let (remaining, keyword) = terminated(alt((keyword("let"), keyword("mut"))), blank)(remaining)?;
+    let (remaining, names) = delimited(tag("("), ws(list), tag(")"))(remaining)?;
+    let (remaining, annotation) = opt(annotation)(remaining)?;
+    let (remaining, _) = ws(tag("="))(remaining)?;
+    let (remaining, value) = value(remaining)?;
+
+    let ids = names.iter().map(|_| Uuid::new_v4().to_string()).collect();
+
+    Ok((
+        remaining,
+        Statement::Destructuring(Destructuring {
+            names,
+            ids,
+            mutable: keyword == "mut",
+            annotation,
+            value: Box::new(value),
+        }),
+    ))
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, opt},
+    error::{VerboseError, VerboseErrorKind},
+    multi::separated_list1,
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+use super::{identifier::identifier, record::field, ws::ws};
+
+/// The type of a value, as written in annotations: `Int`, `List[String]`, `(Int, Float)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    /// An integer without size limit
+    BigInt,
+    String,
+    Boolean,
+    List(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Tuple(Vec<Type>),
+    Closure,
+    /// A reference to a variable holding a value of the type: `Ref[Int]`
+    Reference(Box<Type>),
+    /// A record or a sum type declared by the program
+    Named(String),
+    /// Any type, the type of the values nothing is known about
+    Any,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::BigInt => write!(f, "BigInt"),
+            Type::String => write!(f, "String"),
+            Type::Boolean => write!(f, "Boolean"),
+            Type::List(item) => write!(f, "List[{item}]"),
+            Type::Map(key, value) => write!(f, "Map[{key}, {value}]"),
+            Type::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "({})", items.join(", "))
+            }
+            Type::Closure => write!(f, "Closure"),
+            Type::Reference(value) => write!(f, "Ref[{value}]"),
+            Type::Named(name) => write!(f, "{name}"),
+            Type::Any => write!(f, "Any"),
+        }
+    }
+}
+
+fn parameters(i: &str) -> IResult<&str, Vec<Type>, VerboseError<&str>> {
+    delimited(
+        tag("["),
+        separated_list1(tag(","), ws(type_annotation)),
+        tag("]"),
+    )(i)
+}
+
+/// `Int`, `Point`, a type of an imported module like `shapes.Shape`, or `List`, `Map` and `Ref`
+/// along with the types of their elements: `Map[String, Int]`
+fn named(i: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    let (remaining, name) = identifier(i)?;
+    let (remaining, member) = opt(field)(remaining)?;
+    if let Some(member) = member {
+        return Ok((remaining, Type::Named(format!("{name}.{member}"))));
+    }
+    let (remaining, parameters) = opt(parameters)(remaining)?;
+
+    let annotation = match (name.as_str(), parameters.as_deref()) {
+        ("Int", None) => Type::Int,
+        ("Float", None) => Type::Float,
+        ("BigInt", None) => Type::BigInt,
+        ("String", None) => Type::String,
+        ("Boolean", None) => Type::Boolean,
+        ("Closure", None) => Type::Closure,
+        ("Any", None) => Type::Any,
+        ("List", None) => Type::List(Box::new(Type::Any)),
+        ("List", Some([item])) => Type::List(Box::new(item.clone())),
+        ("Map", None) => Type::Map(Box::new(Type::Any), Box::new(Type::Any)),
+        ("Map", Some([key, value])) => Type::Map(Box::new(key.clone()), Box::new(value.clone())),
+        ("Ref", None) => Type::Reference(Box::new(Type::Any)),
+        ("Ref", Some([value])) => Type::Reference(Box::new(value.clone())),
+        (_, None) => Type::Named(name),
+        _ => {
+            return Err(nom::Err::Error(VerboseError {
+                errors: vec![(
+                    i,
+                    VerboseErrorKind::Context("wrong number of type parameters"),
+                )],
+            }))
+        }
+    };
+
+    Ok((remaining, annotation))
+}
+
+fn tuple(i: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    map(
+        delimited(
+            tag("("),
+            separated_list1(tag(","), ws(type_annotation)),
+            tag(")"),
+        ),
+        Type::Tuple,
+    )(i)
+}
+
+pub fn type_annotation(i: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    alt((tuple, named))(i)
+}
+
+/// The annotation following a name: `: Int` in `let x: Int = 5`
+pub fn annotation(i: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    preceded(ws(char(':')), type_annotation)(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn annotations() {
+        let annotations = [
+            ("Int", Type::Int),
+            ("List[Float]", Type::List(Box::new(Type::Float))),
+            ("List", Type::List(Box::new(Type::Any))),
+            (
+                "Map[String, Ref[Int]]",
+                Type::Map(
+                    Box::new(Type::String),
+                    Box::new(Type::Reference(Box::new(Type::Int))),
+                ),
+            ),
+            (
+                "(Int, Point)",
+                Type::Tuple(vec![Type::Int, Type::Named("Point".to_string())]),
+            ),
+            ("pair", Type::Named("pair".to_string())),
+            ("shapes.Shape", Type::Named("shapes.Shape".to_string())),
+        ];
+
+        for (text, annotation) in annotations {
+            assert_eq!(type_annotation(text), Ok(("", annotation)));
+        }
+
+        assert_eq!(annotation(" : Int = 5"), Ok((" = 5", Type::Int)));
+        assert!(type_annotation("List[Int, Int]").is_err());
+        assert!(type_annotation("Map[Int]").is_err());
+    }
+}
@@ -1,19 +1,77 @@
-use nom::{branch::alt, combinator::map, error::VerboseError, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{map, opt},
+    error::VerboseError,
+    sequence::delimited,
+    IResult,
+};
 
 use super::number::number;
 use super::{
-    boolean::boolean, function_call::function_call, identifier::identifier, statements::Statement,
+    boolean::boolean,
+    closure::closure,
+    function_call::function_call,
+    identifier::identifier,
+    list::{accessors, list, Accessor},
+    macros::macro_call,
+    map::map as map_literal,
+    operations::disjunction,
+    statements::Statement,
     string::string,
+    tuple::tuple,
+    ws::ws,
 };
 
-pub fn value(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
-    let res = alt((
+fn parenthesized(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    delimited(tag("("), ws(value), tag(")"))(i)
+}
+
+fn atom(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((
+        tuple,
+        parenthesized,
+        list,
+        map_literal,
+        macro_call,
         function_call,
         string,
         number,
         boolean,
-        map(identifier, |name| Statement::Variable(name)),
-    ))(i)?;
+        map(identifier, Statement::Variable),
+    ))(i)
+}
+
+/// A single term of an expression, operators are parsed around it. Accessors can follow a `?`:
+/// `get(points, 0)?.x`
+pub fn operand(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (mut remaining, mut value) = atom(i)?;
 
-    Ok(res)
+    loop {
+        let (rest, accessors) = accessors(remaining)?;
+        value = accessors
+            .into_iter()
+            .fold(value, |value, accessor| match accessor {
+                Accessor::Index(index) => Statement::Index(Box::new(value), Box::new(index)),
+                Accessor::Field(name) => Statement::Field(Box::new(value), name),
+            });
+
+        let (rest, propagation) = opt(char('?'))(rest)?;
+        remaining = rest;
+        match propagation {
+            Some(_) => value = Statement::Try(Box::new(value)),
+            None => return Ok((remaining, value)),
+        }
+    }
+}
+
+/// A value that can't be a closure, used where an arrow follows the value (conditions of `if`
+/// and `while`) so that `if done -> ... end` isn't read as a closure taking `done`
+pub fn expression(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    disjunction(i)
+}
+
+pub fn value(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    alt((map(closure, Statement::Closure), expression))(i)
 }
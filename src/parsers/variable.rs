@@ -5,15 +5,26 @@ pub struct Assignement {
     pub id: Option<String>,
     pub value: Box<Statement>,
     pub mutable: bool,
+    /// The type a declaration is annotated with: `Int` in `let x: Int = 5`
+    pub annotation: Option<Type>,
+    /// Accessors of the element being assigned to: `[0]` in `xs[0] = value`, or `.x` in
+    /// `point.x = value`
+    pub accessors: Vec<Accessor>,
+    /// Whether the value is assigned to the variable the reference stored in `name` points to:
+    /// `deref r = value`
+    pub dereference: bool,
 }
 
 use nom::branch::alt;
-use nom::character::complete::multispace0;
 use nom::combinator::opt;
 use nom::error::VerboseError;
-use nom::{self, bytes::complete::tag, IResult};
+use nom::sequence::terminated;
+use nom::{self, IResult};
 
-use crate::parsers::identifier::identifier;
+use crate::parsers::comment::blank;
+use crate::parsers::identifier::{identifier, keyword};
+use crate::parsers::list::{accessors, Accessor};
+use crate::parsers::types::{annotation, Type};
 use crate::parsers::ws::ws;
 use uuid::Uuid;
 
@@ -25,19 +36,13 @@ pub fn variable(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
     let mut id = None;
     let mut mutable = false;
 
-    let (remaining, _) = multispace0(i)?;
-    let (remaining, result) = opt(alt((tag("let"), tag("mut"))))(remaining)?;
+    let (remaining, _) = blank(i)?;
+    let (remaining, result) = opt(alt((keyword("let"), keyword("mut"))))(remaining)?;
     //let (remaining, result) = opt(tag("let"))(remaining)?;
-    let (remaining, _) = multispace0(remaining)?;
+    let (remaining, _) = blank(remaining)?;
 
-    match result {
-        Some(value) => match value {
-            "mut" => {
-                mutable = true;
-            }
-            _ => {}
-        },
-        None => {}
+    if result == Some("mut") {
+        mutable = true;
     }
 
     if result.is_some() {
@@ -45,7 +50,22 @@ pub fn variable(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
         id = Some(Uuid::new_v4().to_string());
     }
 
+    let (remaining, dereference) = if is_declaration {
+        (remaining, None)
+    } else {
+        opt(terminated(keyword("deref"), blank))(remaining)?
+    };
     let (remaining, name) = identifier(remaining)?;
+    let (remaining, accessors) = if is_declaration {
+        (remaining, Vec::new())
+    } else {
+        accessors(remaining)?
+    };
+    let (remaining, annotation) = if is_declaration {
+        opt(annotation)(remaining)?
+    } else {
+        (remaining, None)
+    };
     let (remaining, _) = ws(equals)(remaining)?;
     let (remaining, value) = value(remaining)?;
 
@@ -59,6 +79,9 @@ pub fn variable(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
             mutable,
             declaration: is_declaration,
             value: Box::new(value),
+            accessors,
+            annotation,
+            dereference: dereference.is_some(),
         }),
     ))
 }
@@ -0,0 +1,40 @@
+use nom::{bytes::complete::tag, error::VerboseError, IResult};
+
+use super::{
+    identifier::keyword,
+    statements::{statements, Statement, Statements},
+    value::expression,
+    ws::ws,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhileLoop {
+    pub condition: Box<Statement>,
+    pub body: Statements,
+}
+
+pub fn while_loop(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("while"))(i)?;
+    let (remaining, condition) = ws(expression)(remaining)?;
+    let (remaining, _) = ws(tag("->"))(remaining)?;
+    let (remaining, body) = ws(statements)(remaining)?;
+    let (remaining, _) = ws(keyword("end"))(remaining)?;
+
+    Ok((
+        remaining,
+        Statement::While(WhileLoop {
+            condition: Box::new(condition),
+            body,
+        }),
+    ))
+}
+
+pub fn break_statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("break"))(i)?;
+    Ok((remaining, Statement::Break))
+}
+
+pub fn continue_statement(i: &str) -> IResult<&str, Statement, VerboseError<&str>> {
+    let (remaining, _) = ws(keyword("continue"))(i)?;
+    Ok((remaining, Statement::Continue))
+}
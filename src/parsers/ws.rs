@@ -1,10 +1,17 @@
-use nom::{character::complete::multispace0, error::ParseError, sequence::delimited, IResult};
+use nom::{
+    error::{ContextError, ParseError},
+    sequence::delimited,
+    IResult,
+};
 
-pub fn ws<'a, F: 'a, O, E: ParseError<&'a str>>(
+use super::comment::blank;
+
+/// Surrounds a parser with whitespace and comments
+pub fn ws<'a, F, O, E: ParseError<&'a str> + ContextError<&'a str>>(
     inner: F,
 ) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
 where
-    F: Fn(&'a str) -> IResult<&'a str, O, E>,
+    F: Fn(&'a str) -> IResult<&'a str, O, E> + 'a,
 {
-    delimited(multispace0, inner, multispace0)
+    delimited(blank, inner, blank)
 }